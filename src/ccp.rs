@@ -0,0 +1,810 @@
+pub mod compression;
+pub mod deflate;
+pub mod mppe;
+
+use crate::{
+    ControlMsg, ControlPacket, Deserialize, Error, Result, Serialize, CODE_REJECT_HEADER_LEN,
+};
+
+use std::fmt;
+use std::io::{Read, Write};
+
+use ppproperly_macros::{Deserialize, Serialize};
+
+pub const CCP_CONFIGURE_REQUEST: u8 = 1;
+pub const CCP_CONFIGURE_ACK: u8 = 2;
+pub const CCP_CONFIGURE_NAK: u8 = 3;
+pub const CCP_CONFIGURE_REJECT: u8 = 4;
+pub const CCP_TERMINATE_REQUEST: u8 = 5;
+pub const CCP_TERMINATE_ACK: u8 = 6;
+pub const CCP_CODE_REJECT: u8 = 7;
+pub const CCP_RESET_REQUEST: u8 = 14;
+pub const CCP_RESET_ACK: u8 = 15;
+
+pub const OPT_MPPE: u8 = 18;
+pub const OPT_BSD_COMPRESS: u8 = 21;
+pub const OPT_DEFLATE: u8 = 26;
+
+pub const BSD_COMPRESS_VERSION: u8 = 1;
+pub const DEFLATE_METHOD: u8 = 8;
+pub const DEFLATE_CHECK_SEQUENCE: u8 = 0;
+
+pub const MPPE_STATELESS: u32 = 0x0100_0000;
+pub const MPPE_56_BIT: u32 = 0x80;
+pub const MPPE_128_BIT: u32 = 0x40;
+pub const MPPE_40_BIT: u32 = 0x20;
+pub const MPPE_MPPC: u32 = 0x01;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CcpOpt {
+    Mppe(Mppe),
+    BsdCompress(BsdCompress),
+    Deflate(Deflate),
+    Unhandled(u8, Vec<u8>),
+}
+
+impl Serialize for CcpOpt {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Self::Mppe(payload) => payload.serialize(w),
+            Self::BsdCompress(payload) => payload.serialize(w),
+            Self::Deflate(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
+        }
+    }
+}
+
+impl CcpOpt {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::Mppe(_) => OPT_MPPE,
+            Self::BsdCompress(_) => OPT_BSD_COMPRESS,
+            Self::Deflate(_) => OPT_DEFLATE,
+            Self::Unhandled(ty, _) => *ty,
+        }
+    }
+
+    fn len(&self) -> u8 {
+        match self {
+            Self::Mppe(_) => 4,
+            Self::BsdCompress(_) => 1,
+            Self::Deflate(_) => 2,
+            Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!(
+                    "unhandled ccp option {} length {} exceeds 255",
+                    *ty,
+                    payload.len()
+                )
+            }),
+        }
+    }
+
+    fn deserialize_with_discriminant<R: Read>(
+        &mut self,
+        r: &mut R,
+        discriminant: &u8,
+    ) -> Result<()> {
+        match *discriminant {
+            OPT_MPPE => {
+                let mut tmp = Mppe::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Mppe(tmp);
+            }
+            OPT_BSD_COMPRESS => {
+                let mut tmp = BsdCompress::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::BsdCompress(tmp);
+            }
+            OPT_DEFLATE => {
+                let mut tmp = Deflate::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Deflate(tmp);
+            }
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(*discriminant, tmp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Microsoft Point-To-Point Encryption option as described in RFC 3078.
+/// Each flag corresponds to one of the `MPPE_*` bits, the obsolete D bit is ignored.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Mppe {
+    /// Rekey after every packet instead of every 256 packets.
+    pub stateless: bool,
+    /// Microsoft Point-to-Point Compression, which isn't implemented.
+    pub mppc: bool,
+    pub bits_40: bool,
+    pub bits_56: bool,
+    pub bits_128: bool,
+}
+
+impl Serialize for Mppe {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut bits = 0;
+        for (set, bit) in [
+            (self.stateless, MPPE_STATELESS),
+            (self.mppc, MPPE_MPPC),
+            (self.bits_40, MPPE_40_BIT),
+            (self.bits_56, MPPE_56_BIT),
+            (self.bits_128, MPPE_128_BIT),
+        ] {
+            if set {
+                bits |= bit;
+            }
+        }
+
+        bits.serialize(w)
+    }
+}
+
+impl Deserialize for Mppe {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut bits = u32::default();
+        bits.deserialize(r)?;
+
+        self.stateless = bits & MPPE_STATELESS != 0;
+        self.mppc = bits & MPPE_MPPC != 0;
+        self.bits_40 = bits & MPPE_40_BIT != 0;
+        self.bits_56 = bits & MPPE_56_BIT != 0;
+        self.bits_128 = bits & MPPE_128_BIT != 0;
+        Ok(())
+    }
+}
+
+/// BSD-Compress option as described in RFC 1977.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BsdCompress {
+    /// Always [`BSD_COMPRESS_VERSION`].
+    pub version: u8,
+    /// Maximum code size in bits, 9 to 16.
+    pub dict_bits: u8,
+}
+
+impl Default for BsdCompress {
+    fn default() -> Self {
+        Self {
+            version: BSD_COMPRESS_VERSION,
+            dict_bits: 15,
+        }
+    }
+}
+
+impl Serialize for BsdCompress {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        ((self.version & 0x07) << 5 | self.dict_bits & 0x1f).serialize(w)
+    }
+}
+
+impl Deserialize for BsdCompress {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut value = u8::default();
+        value.deserialize(r)?;
+
+        self.version = value >> 5;
+        self.dict_bits = value & 0x1f;
+        Ok(())
+    }
+}
+
+/// Deflate option as described in RFC 1979.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Deflate {
+    /// Base two logarithm of the LZ77 window size, 8 to 15.
+    pub window: u8,
+    /// Always [`DEFLATE_METHOD`].
+    pub method: u8,
+    /// Always [`DEFLATE_CHECK_SEQUENCE`].
+    pub check: u8,
+}
+
+impl Default for Deflate {
+    fn default() -> Self {
+        Self {
+            window: 15,
+            method: DEFLATE_METHOD,
+            check: DEFLATE_CHECK_SEQUENCE,
+        }
+    }
+}
+
+impl Serialize for Deflate {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        (self.window.wrapping_sub(8) << 4 | self.method & 0x0f).serialize(w)?;
+        (self.check & 0x03).serialize(w)
+    }
+}
+
+impl Deserialize for Deflate {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut value = u8::default();
+        value.deserialize(r)?;
+
+        self.window = (value >> 4) + 8;
+        self.method = value & 0x0f;
+
+        self.check.deserialize(r)?;
+        self.check &= 0x03;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpOption {
+    #[ppproperly(discriminant_for(field = "value", data_type = "u8"))]
+    #[ppproperly(len_for(field = "value", offset = 2, data_type = "u8"))]
+    pub value: CcpOpt,
+}
+
+impl CcpOption {
+    pub fn len(&self) -> u8 {
+        2 + self.value.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 2
+    }
+}
+
+impl From<CcpOpt> for CcpOption {
+    fn from(value: CcpOpt) -> Self {
+        Self { value }
+    }
+}
+
+impl Serialize for [CcpOption] {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        for option in self {
+            option.serialize(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Deserialize for Vec<CcpOption> {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+
+        let mut r = buf.as_slice();
+        while !r.is_empty() {
+            let mut tmp = CcpOption::from(CcpOpt::Deflate(Deflate::default()));
+
+            tmp.deserialize(&mut r)?;
+            self.push(tmp);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CcpData {
+    ConfigureRequest(CcpConfigureRequest),
+    ConfigureAck(CcpConfigureAck),
+    ConfigureNak(CcpConfigureNak),
+    ConfigureReject(CcpConfigureReject),
+    TerminateRequest(CcpTerminateRequest),
+    TerminateAck(CcpTerminateAck),
+    CodeReject(CcpCodeReject),
+    ResetRequest(CcpResetRequest),
+    ResetAck(CcpResetAck),
+    Unhandled(u8, Vec<u8>),
+}
+
+impl Default for CcpData {
+    fn default() -> Self {
+        Self::ConfigureRequest(CcpConfigureRequest::default())
+    }
+}
+
+impl Serialize for CcpData {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Self::ConfigureRequest(payload) => payload.serialize(w),
+            Self::ConfigureAck(payload) => payload.serialize(w),
+            Self::ConfigureNak(payload) => payload.serialize(w),
+            Self::ConfigureReject(payload) => payload.serialize(w),
+            Self::TerminateRequest(payload) => payload.serialize(w),
+            Self::TerminateAck(payload) => payload.serialize(w),
+            Self::CodeReject(payload) => payload.serialize(w),
+            Self::ResetRequest(payload) => payload.serialize(w),
+            Self::ResetAck(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
+        }
+    }
+}
+
+impl CcpData {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::ConfigureRequest(_) => CCP_CONFIGURE_REQUEST,
+            Self::ConfigureAck(_) => CCP_CONFIGURE_ACK,
+            Self::ConfigureNak(_) => CCP_CONFIGURE_NAK,
+            Self::ConfigureReject(_) => CCP_CONFIGURE_REJECT,
+            Self::TerminateRequest(_) => CCP_TERMINATE_REQUEST,
+            Self::TerminateAck(_) => CCP_TERMINATE_ACK,
+            Self::CodeReject(_) => CCP_CODE_REJECT,
+            Self::ResetRequest(_) => CCP_RESET_REQUEST,
+            Self::ResetAck(_) => CCP_RESET_ACK,
+            Self::Unhandled(ty, _) => *ty,
+        }
+    }
+
+    fn len(&self) -> u16 {
+        match self {
+            Self::ConfigureRequest(payload) => payload.len(),
+            Self::ConfigureAck(payload) => payload.len(),
+            Self::ConfigureNak(payload) => payload.len(),
+            Self::ConfigureReject(payload) => payload.len(),
+            Self::TerminateRequest(payload) => payload.len(),
+            Self::TerminateAck(payload) => payload.len(),
+            Self::CodeReject(payload) => payload.len(),
+            Self::ResetRequest(payload) => payload.len(),
+            Self::ResetAck(payload) => payload.len(),
+            Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!(
+                    "unhandled ccp code {} length {} exceeds 65535",
+                    *ty,
+                    payload.len()
+                )
+            }),
+        }
+    }
+
+    fn deserialize_with_discriminant<R: Read>(
+        &mut self,
+        r: &mut R,
+        discriminant: &u8,
+    ) -> Result<()> {
+        match *discriminant {
+            CCP_CONFIGURE_REQUEST => {
+                let mut tmp = CcpConfigureRequest::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureRequest(tmp);
+            }
+            CCP_CONFIGURE_ACK => {
+                let mut tmp = CcpConfigureAck::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureAck(tmp);
+            }
+            CCP_CONFIGURE_NAK => {
+                let mut tmp = CcpConfigureNak::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureNak(tmp);
+            }
+            CCP_CONFIGURE_REJECT => {
+                let mut tmp = CcpConfigureReject::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureReject(tmp);
+            }
+            CCP_TERMINATE_REQUEST => {
+                let mut tmp = CcpTerminateRequest::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::TerminateRequest(tmp);
+            }
+            CCP_TERMINATE_ACK => {
+                let mut tmp = CcpTerminateAck::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::TerminateAck(tmp);
+            }
+            CCP_CODE_REJECT => {
+                let mut tmp = CcpCodeReject::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::CodeReject(tmp);
+            }
+            CCP_RESET_REQUEST => {
+                let mut tmp = CcpResetRequest::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ResetRequest(tmp);
+            }
+            CCP_RESET_ACK => {
+                let mut tmp = CcpResetAck::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ResetAck(tmp);
+            }
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(*discriminant, tmp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpPkt {
+    #[ppproperly(discriminant_for(field = "data", data_type = "u8"))]
+    pub identifier: u8,
+    #[ppproperly(len_for(field = "data", offset = 4, data_type = "u16"))]
+    pub data: CcpData,
+}
+
+impl CcpPkt {
+    pub fn new_configure_request(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::ConfigureRequest(CcpConfigureRequest { options }),
+        }
+    }
+
+    pub fn new_configure_ack(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::ConfigureAck(CcpConfigureAck { options }),
+        }
+    }
+
+    pub fn new_configure_nak(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::ConfigureNak(CcpConfigureNak { options }),
+        }
+    }
+
+    pub fn new_configure_reject(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::ConfigureReject(CcpConfigureReject { options }),
+        }
+    }
+
+    pub fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::TerminateRequest(CcpTerminateRequest { data }),
+        }
+    }
+
+    pub fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::TerminateAck(CcpTerminateAck { data }),
+        }
+    }
+
+    pub fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::CodeReject(CcpCodeReject { pkt }),
+        }
+    }
+
+    pub fn new_reset_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::ResetRequest(CcpResetRequest { data }),
+        }
+    }
+
+    pub fn new_reset_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: CcpData::ResetAck(CcpResetAck { data }),
+        }
+    }
+
+    /// Builds a Code-Reject of `rejected`, truncated to fit the peer's `mru`.
+    pub fn new_code_reject_for(identifier: u8, rejected: &CcpPkt, mru: u16) -> Result<Self> {
+        let mut pkt = Vec::new();
+        rejected.serialize(&mut pkt)?;
+
+        pkt.truncate(usize::from(mru).saturating_sub(CODE_REJECT_HEADER_LEN));
+        Ok(Self::new_code_reject(identifier, pkt))
+    }
+
+    pub fn len(&self) -> u16 {
+        4 + self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 4
+    }
+}
+
+impl ControlPacket for CcpPkt {
+    type Option = CcpOption;
+
+    fn new_configure_request(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self::new_configure_request(identifier, options)
+    }
+
+    fn new_configure_ack(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self::new_configure_ack(identifier, options)
+    }
+
+    fn new_configure_nak(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self::new_configure_nak(identifier, options)
+    }
+
+    fn new_configure_reject(identifier: u8, options: Vec<CcpOption>) -> Self {
+        Self::new_configure_reject(identifier, options)
+    }
+
+    fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_request(identifier, data)
+    }
+
+    fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_ack(identifier, data)
+    }
+
+    fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self::new_code_reject(identifier, pkt)
+    }
+
+    fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    fn msg(&self) -> ControlMsg<'_, CcpOption> {
+        match &self.data {
+            CcpData::ConfigureRequest(payload) => ControlMsg::ConfigureRequest(&payload.options),
+            CcpData::ConfigureAck(payload) => ControlMsg::ConfigureAck(&payload.options),
+            CcpData::ConfigureNak(payload) => ControlMsg::ConfigureNak(&payload.options),
+            CcpData::ConfigureReject(payload) => ControlMsg::ConfigureReject(&payload.options),
+            CcpData::TerminateRequest(_) => ControlMsg::TerminateRequest,
+            CcpData::TerminateAck(_) => ControlMsg::TerminateAck,
+            CcpData::CodeReject(payload) => ControlMsg::CodeReject(&payload.pkt),
+            CcpData::ResetRequest(_) | CcpData::ResetAck(_) => ControlMsg::Other,
+            CcpData::Unhandled(..) => ControlMsg::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for CcpPkt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CCP id={}: ", self.identifier)?;
+        match &self.data {
+            CcpData::ConfigureRequest(cfg_req) => cfg_req.fmt(f),
+            CcpData::ConfigureAck(cfg_ack) => cfg_ack.fmt(f),
+            CcpData::ConfigureNak(cfg_nak) => cfg_nak.fmt(f),
+            CcpData::ConfigureReject(cfg_rej) => cfg_rej.fmt(f),
+            CcpData::TerminateRequest(term_req) => term_req.fmt(f),
+            CcpData::TerminateAck(term_ack) => term_ack.fmt(f),
+            CcpData::CodeReject(code_rej) => code_rej.fmt(f),
+            CcpData::ResetRequest(reset_req) => reset_req.fmt(f),
+            CcpData::ResetAck(reset_ack) => reset_ack.fmt(f),
+            CcpData::Unhandled(ty, payload) => write!(f, "uc={} {:?}", ty, payload),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpConfigureRequest {
+    pub options: Vec<CcpOption>,
+}
+
+impl CcpConfigureRequest {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for CcpConfigureRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cfg-Req {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpConfigureAck {
+    pub options: Vec<CcpOption>,
+}
+
+impl CcpConfigureAck {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for CcpConfigureAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cfg-Ack {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpConfigureNak {
+    pub options: Vec<CcpOption>,
+}
+
+impl CcpConfigureNak {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for CcpConfigureNak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cfg-Nak {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpConfigureReject {
+    pub options: Vec<CcpOption>,
+}
+
+impl CcpConfigureReject {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for CcpConfigureReject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cfg-Rej {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpTerminateRequest {
+    pub data: Vec<u8>,
+}
+
+impl CcpTerminateRequest {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for CcpTerminateRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Term-Req {}",
+            std::str::from_utf8(&self.data).unwrap_or(&format!("{:?}", self.data))
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpTerminateAck {
+    pub data: Vec<u8>,
+}
+
+impl CcpTerminateAck {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for CcpTerminateAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Term-Ack {}",
+            std::str::from_utf8(&self.data).unwrap_or(&format!("{:?}", self.data))
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpCodeReject {
+    pub pkt: Vec<u8>, // Vec makes MRU truncating easier without overwriting (de)ser impls.
+}
+
+impl CcpCodeReject {
+    pub fn len(&self) -> u16 {
+        self.pkt.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pkt.is_empty()
+    }
+}
+
+impl fmt::Display for CcpCodeReject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Code-Rej {:?}", self.pkt)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpResetRequest {
+    pub data: Vec<u8>,
+}
+
+impl CcpResetRequest {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for CcpResetRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reset-Req {:?}", self.data)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CcpResetAck {
+    pub data: Vec<u8>,
+}
+
+impl CcpResetAck {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for CcpResetAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reset-Ack {:?}", self.data)
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{CcpData, CcpPkt, PppData, PppPkt, Result};
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Compressing half of an algorithm negotiated through CCP.
+pub trait Compressor: fmt::Debug {
+    /// Returns the packet to send instead of `pkt`, usually a compressed
+    /// datagram or `pkt` itself if it can't be compressed.
+    fn compress(&mut self, pkt: &PppPkt) -> Result<PppPkt>;
+
+    /// Clears the history after a Reset-Request of the peer.
+    fn reset(&mut self);
+}
+
+/// Decompressing half of an algorithm negotiated through CCP.
+pub trait Decompressor: fmt::Debug {
+    /// Decompresses the payload of a compressed datagram.
+    fn decompress(&mut self, data: &[u8]) -> Result<PppPkt>;
+
+    /// Handles a packet the peer sent without compression.
+    /// Algorithms with a history may need to add it.
+    fn handle_uncompressed(&mut self, _pkt: &PppPkt) -> Result<()> {
+        Ok(())
+    }
+
+    /// Whether a packet the peer sent without compression is acceptable.
+    /// Encryption refuses the protocols it would have encrypted.
+    fn accepts_uncompressed(&self, _pkt: &PppPkt) -> bool {
+        true
+    }
+
+    /// Whether compressed datagrams are discarded until the peer acknowledges
+    /// our Reset-Request. Algorithms that resynchronize by themselves,
+    /// e.g. MPPE, keep decompressing instead.
+    fn waits_for_reset_ack(&self) -> bool {
+        true
+    }
+
+    /// Clears the history once the peer acknowledged our Reset-Request.
+    fn reset(&mut self);
+}
+
+/// Settings of a [`Compression`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CompressionConfig {
+    /// Retransmission interval of Reset-Requests.
+    pub restart_timer: Duration,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            restart_timer: Duration::from_secs(3),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CompressionStats {
+    pub packets_compressed: u64,
+    pub packets_decompressed: u64,
+    /// Number of compressed datagrams that failed to decompress
+    /// or were dropped while waiting for a Reset-Ack.
+    pub packets_discarded: u64,
+    pub resets_sent: u64,
+    pub resets_received: u64,
+}
+
+/// Sans-IO compressed datagram handling as described in RFC 1962.
+///
+/// Outgoing packets are passed through [`Compression::compress`], received
+/// ones through [`Compression::handle_packet`] which returns them decompressed.
+/// If a compressed datagram can't be decompressed, a Reset-Request is sent
+/// and compressed datagrams are discarded until the peer acknowledges it.
+/// CCP packets to send are returned by [`Compression::poll_transmit`].
+///
+/// Compression is disabled in either direction until an algorithm is set,
+/// e.g. once CCP is opened.
+#[derive(Debug, Default)]
+pub struct Compression {
+    config: CompressionConfig,
+    compressor: Option<Box<dyn Compressor>>,
+    decompressor: Option<Box<dyn Decompressor>>,
+    reset_identifier: u8,
+    reset_deadline: Option<Instant>,
+    stats: CompressionStats,
+    transmit: VecDeque<CcpPkt>,
+}
+
+impl Compression {
+    pub fn new(config: CompressionConfig) -> Self {
+        Self {
+            config,
+            compressor: None,
+            decompressor: None,
+            reset_identifier: 0,
+            reset_deadline: None,
+            stats: CompressionStats::default(),
+            transmit: VecDeque::new(),
+        }
+    }
+
+    /// Sets the algorithm the peer accepted, `None` disables compression.
+    pub fn set_compressor(&mut self, compressor: Option<Box<dyn Compressor>>) {
+        self.compressor = compressor;
+    }
+
+    /// Sets the algorithm we accepted, `None` disables decompression.
+    pub fn set_decompressor(&mut self, decompressor: Option<Box<dyn Decompressor>>) {
+        self.decompressor = decompressor;
+        self.reset_deadline = None;
+    }
+
+    /// Returns the packet to send instead of `pkt`.
+    pub fn compress(&mut self, pkt: &PppPkt) -> Result<PppPkt> {
+        match &mut self.compressor {
+            Some(compressor) => {
+                let pkt = compressor.compress(pkt)?;
+                if let PppData::Compressed(_) = pkt.data {
+                    self.stats.packets_compressed += 1;
+                }
+
+                Ok(pkt)
+            }
+            None => Ok(pkt.clone()),
+        }
+    }
+
+    /// Handles a received packet, returning it decompressed if it is meant
+    /// for the upper layers. Reset-Requests and Reset-Acks are consumed,
+    /// any other CCP packets are returned for option negotiation.
+    pub fn handle_packet(&mut self, pkt: PppPkt, now: Instant) -> Option<PppPkt> {
+        match &pkt.data {
+            PppData::Ccp(CcpPkt {
+                identifier,
+                data: CcpData::ResetRequest(_),
+            }) => {
+                if let Some(compressor) = &mut self.compressor {
+                    compressor.reset();
+                }
+
+                self.stats.resets_received += 1;
+                self.transmit
+                    .push_back(CcpPkt::new_reset_ack(*identifier, Vec::new()));
+                None
+            }
+            PppData::Ccp(CcpPkt {
+                identifier,
+                data: CcpData::ResetAck(_),
+            }) => {
+                if self.reset_deadline.is_some() && *identifier == self.reset_identifier {
+                    if let Some(decompressor) = &mut self.decompressor {
+                        if decompressor.waits_for_reset_ack() {
+                            decompressor.reset();
+                        }
+                    }
+
+                    self.reset_deadline = None;
+                }
+
+                None
+            }
+            PppData::Compressed(data) => {
+                let decompressor = match &mut self.decompressor {
+                    Some(decompressor)
+                        if self.reset_deadline.is_none() || !decompressor.waits_for_reset_ack() =>
+                    {
+                        decompressor
+                    }
+                    _ => {
+                        self.stats.packets_discarded += 1;
+                        return None;
+                    }
+                };
+
+                match decompressor.decompress(data) {
+                    Ok(pkt) => {
+                        // Resynchronized without a Reset-Ack.
+                        self.reset_deadline = None;
+
+                        self.stats.packets_decompressed += 1;
+                        Some(pkt)
+                    }
+                    Err(_) => {
+                        self.stats.packets_discarded += 1;
+                        if self.reset_deadline.is_none() {
+                            self.send_reset_request(now);
+                        }
+
+                        None
+                    }
+                }
+            }
+            _ => {
+                if let Some(decompressor) = &mut self.decompressor {
+                    if !decompressor.accepts_uncompressed(&pkt) {
+                        self.stats.packets_discarded += 1;
+                        return None;
+                    }
+
+                    if self.reset_deadline.is_none()
+                        && decompressor.handle_uncompressed(&pkt).is_err()
+                    {
+                        self.send_reset_request(now);
+                    }
+                }
+
+                Some(pkt)
+            }
+        }
+    }
+
+    /// Retransmits the pending Reset-Request if the peer hasn't acknowledged it in time.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        match self.reset_deadline {
+            Some(deadline) if deadline <= now => {
+                self.transmit
+                    .push_back(CcpPkt::new_reset_request(self.reset_identifier, Vec::new()));
+                self.reset_deadline = Some(now + self.config.restart_timer);
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns the point in time at which [`Compression::handle_timeout`] needs to be called.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        self.reset_deadline
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<CcpPkt> {
+        self.transmit.pop_front()
+    }
+
+    pub fn stats(&self) -> &CompressionStats {
+        &self.stats
+    }
+
+    fn send_reset_request(&mut self, now: Instant) {
+        self.reset_identifier = self.reset_identifier.wrapping_add(1);
+        self.reset_deadline = Some(now + self.config.restart_timer);
+        self.stats.resets_sent += 1;
+
+        self.transmit
+            .push_back(CcpPkt::new_reset_request(self.reset_identifier, Vec::new()));
+    }
+}
@@ -0,0 +1,198 @@
+use super::compression::{Compressor, Decompressor};
+use crate::{Error, Framing, PppPkt, Result, COMPRESSED_DATAGRAM};
+
+use flate2::{Compress, Compression, Decompress, FlushCompress, FlushDecompress};
+
+/// Trailer of a sync flush, omitted from compressed datagrams.
+const SYNC_MARKER: [u8; 4] = [0x00, 0x00, 0xff, 0xff];
+
+/// Largest packet a compressed datagram may expand to.
+const MAX_DECOMPRESSED_LEN: usize = 65535 + 2;
+
+/// Protocol numbers of packets carried inside compressed datagrams.
+fn is_compressible(protocol: u16) -> bool {
+    // Individual-link compressed datagrams use 0x00fb.
+    protocol <= 0x3fff && protocol != COMPRESSED_DATAGRAM && protocol != 0x00fb
+}
+
+/// Framing of packets inside compressed datagrams.
+fn inner_framing() -> Framing {
+    Framing {
+        pfc: true,
+        ..Default::default()
+    }
+}
+
+/// Deflate compressor as described in RFC 1979.
+///
+/// Always uses a 32 KiB window, so the peer has to accept
+/// a Deflate option with a window of 15.
+#[derive(Debug)]
+pub struct DeflateCompressor {
+    compress: Compress,
+    sequence: u16,
+}
+
+impl Default for DeflateCompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeflateCompressor {
+    pub fn new() -> Self {
+        Self {
+            compress: Compress::new(Compression::default(), false),
+            sequence: 0,
+        }
+    }
+}
+
+impl Compressor for DeflateCompressor {
+    fn compress(&mut self, pkt: &PppPkt) -> Result<PppPkt> {
+        if !is_compressible(pkt.protocol()) {
+            return Ok(pkt.clone());
+        }
+
+        let mut input = Vec::new();
+        pkt.serialize_framed(&mut input, &inner_framing())?;
+
+        let mut out = Vec::with_capacity(2 + input.len() + input.len() / 8 + 64);
+        out.extend_from_slice(&self.sequence.to_be_bytes());
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let start = self.compress.total_in();
+        loop {
+            let consumed = (self.compress.total_in() - start) as usize;
+            self.compress
+                .compress_vec(&input[consumed..], &mut out, FlushCompress::Sync)?;
+
+            let consumed = (self.compress.total_in() - start) as usize;
+            if consumed == input.len() && out.len() < out.capacity() {
+                break;
+            }
+
+            out.reserve(out.capacity());
+        }
+
+        if out.ends_with(&SYNC_MARKER) {
+            out.truncate(out.len() - SYNC_MARKER.len());
+        }
+
+        // The data is part of the history either way, the peer
+        // adds it to its history when receiving it uncompressed.
+        if out.len() > input.len() {
+            Ok(pkt.clone())
+        } else {
+            Ok(PppPkt::new_compressed(out))
+        }
+    }
+
+    fn reset(&mut self) {
+        self.compress.reset();
+        self.sequence = 0;
+    }
+}
+
+/// Deflate decompressor as described in RFC 1979.
+/// Accepts any window size.
+#[derive(Debug)]
+pub struct DeflateDecompressor {
+    decompress: Decompress,
+    sequence: u16,
+}
+
+impl Default for DeflateDecompressor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeflateDecompressor {
+    pub fn new() -> Self {
+        Self {
+            decompress: Decompress::new(false),
+            sequence: 0,
+        }
+    }
+
+    fn inflate(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(input.len() * 4 + 64);
+
+        let start = self.decompress.total_in();
+        loop {
+            let consumed = (self.decompress.total_in() - start) as usize;
+            let total_out = self.decompress.total_out();
+
+            self.decompress
+                .decompress_vec(&input[consumed..], &mut out, FlushDecompress::Sync)?;
+
+            let progress = (self.decompress.total_in() - start) as usize != consumed
+                || self.decompress.total_out() != total_out;
+            let consumed = (self.decompress.total_in() - start) as usize;
+
+            if consumed == input.len() && out.len() < out.capacity() {
+                return Ok(out);
+            }
+            if out.len() > MAX_DECOMPRESSED_LEN {
+                return Err(Error::DecompressedTooLong);
+            }
+            if !progress && out.len() < out.capacity() {
+                return Err(Error::CompressedDatagramTooShort(input.len()));
+            }
+
+            out.reserve(out.capacity());
+        }
+    }
+}
+
+impl Decompressor for DeflateDecompressor {
+    fn decompress(&mut self, data: &[u8]) -> Result<PppPkt> {
+        if data.len() < 2 {
+            return Err(Error::CompressedDatagramTooShort(data.len()));
+        }
+
+        let sequence = u16::from_be_bytes([data[0], data[1]]);
+        if sequence != self.sequence {
+            return Err(Error::UnexpectedCompressionSequence(sequence));
+        }
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let mut input = Vec::with_capacity(data.len() + SYNC_MARKER.len());
+        input.extend_from_slice(&data[2..]);
+        input.extend_from_slice(&SYNC_MARKER);
+
+        let payload = self.inflate(&input)?;
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize_framed(&mut payload.as_slice(), &inner_framing())?;
+
+        Ok(pkt)
+    }
+
+    fn handle_uncompressed(&mut self, pkt: &PppPkt) -> Result<()> {
+        if !is_compressible(pkt.protocol()) {
+            return Ok(());
+        }
+
+        let mut data = Vec::new();
+        pkt.serialize_framed(&mut data, &inner_framing())?;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        // Feed the data into the history as a stored block.
+        let len = u16::try_from(data.len())?;
+        let mut input = Vec::with_capacity(5 + data.len());
+        input.push(0x00);
+        input.extend_from_slice(&len.to_le_bytes());
+        input.extend_from_slice(&(!len).to_le_bytes());
+        input.extend_from_slice(&data);
+
+        self.inflate(&input)?;
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.decompress.reset(false);
+        self.sequence = 0;
+    }
+}
@@ -0,0 +1,352 @@
+use super::compression::{Compressor, Decompressor};
+use crate::{Error, Framing, PppPkt, Result};
+
+use md4::{Digest, Md4};
+use sha1::Sha1;
+
+const MPPE_FLUSHED: u16 = 0x8000;
+const MPPE_COMPRESSED: u16 = 0x2000;
+const MPPE_ENCRYPTED: u16 = 0x1000;
+const MPPE_CCOUNT_MASK: u16 = 0x0fff;
+
+const SHS_PAD1: [u8; 40] = [0x00; 40];
+const SHS_PAD2: [u8; 40] = [0xf2; 40];
+
+const MAGIC1: &[u8; 27] = b"This is the MPPE Master Key";
+const MAGIC2: &[u8; 84] =
+    b"On the client side, this is the send key; on the server side, it is the receive key.";
+const MAGIC3: &[u8; 84] =
+    b"On the client side, this is the receive key; on the server side, it is the send key.";
+
+/// Protocol numbers MPPE encrypts.
+fn is_encrypted(protocol: u16) -> bool {
+    (0x0021..=0x00fa).contains(&protocol)
+}
+
+/// Session key length negotiated through the [`crate::Mppe`] option.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum MppeStrength {
+    Bits40,
+    Bits56,
+    #[default]
+    Bits128,
+}
+
+impl MppeStrength {
+    fn key_len(&self) -> usize {
+        match self {
+            Self::Bits40 | Self::Bits56 => 8,
+            Self::Bits128 => 16,
+        }
+    }
+}
+
+/// Returns the MD4 hash of the UTF-16LE encoded password as used by MS-CHAP.
+pub fn nt_password_hash(password: &str) -> [u8; 16] {
+    let unicode: Vec<u8> = password.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    Md4::digest(unicode).into()
+}
+
+/// Start keys of both directions derived from MS-CHAPv2 as described in RFC 3079.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MppeKeys {
+    pub send: [u8; 16],
+    pub receive: [u8; 16],
+}
+
+impl MppeKeys {
+    /// Derives the keys from the password and the NT-Response
+    /// of a successful MS-CHAPv2 authentication.
+    pub fn from_password(password: &str, nt_response: &[u8; 24], is_server: bool) -> Self {
+        Self::from_password_hash(&nt_password_hash(password), nt_response, is_server)
+    }
+
+    /// Derives the keys from the NT password hash instead of the password itself,
+    /// e.g. on a server that only stores the hash.
+    pub fn from_password_hash(
+        password_hash: &[u8; 16],
+        nt_response: &[u8; 24],
+        is_server: bool,
+    ) -> Self {
+        let password_hash_hash = Md4::digest(password_hash);
+
+        let mut sha = Sha1::new();
+        sha.update(password_hash_hash);
+        sha.update(nt_response);
+        sha.update(MAGIC1);
+        let master_key = &sha.finalize()[..16];
+
+        let start_key = |magic: &[u8]| {
+            let mut sha = Sha1::new();
+            sha.update(master_key);
+            sha.update(SHS_PAD1);
+            sha.update(magic);
+            sha.update(SHS_PAD2);
+
+            let mut key = [0; 16];
+            key.copy_from_slice(&sha.finalize()[..16]);
+            key
+        };
+
+        let (send, receive) = if is_server {
+            (MAGIC3, MAGIC2)
+        } else {
+            (MAGIC2, MAGIC3)
+        };
+
+        Self {
+            send: start_key(send),
+            receive: start_key(receive),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Rc4 {
+    s: [u8; 256],
+    i: u8,
+    j: u8,
+}
+
+impl Rc4 {
+    fn new(key: &[u8]) -> Self {
+        let mut s = [0; 256];
+        for (i, x) in s.iter_mut().enumerate() {
+            *x = i as u8;
+        }
+
+        let mut j = 0u8;
+        for i in 0..256 {
+            j = j.wrapping_add(s[i]).wrapping_add(key[i % key.len()]);
+            s.swap(i, j.into());
+        }
+
+        Self { s, i: 0, j: 0 }
+    }
+
+    fn apply(&mut self, data: &mut [u8]) {
+        for byte in data {
+            self.i = self.i.wrapping_add(1);
+            self.j = self.j.wrapping_add(self.s[usize::from(self.i)]);
+            self.s.swap(self.i.into(), self.j.into());
+
+            let k = self.s[usize::from(
+                self.s[usize::from(self.i)].wrapping_add(self.s[usize::from(self.j)]),
+            )];
+            *byte ^= k;
+        }
+    }
+}
+
+/// Session key and cipher state of one direction.
+#[derive(Clone)]
+struct MppeState {
+    strength: MppeStrength,
+    start_key: [u8; 16],
+    session_key: [u8; 16],
+    rc4: Rc4,
+    stateless: bool,
+    ccount: u16,
+}
+
+impl MppeState {
+    fn new(start_key: &[u8; 16], strength: MppeStrength, stateless: bool) -> Self {
+        let mut state = Self {
+            strength,
+            start_key: *start_key,
+            session_key: *start_key,
+            rc4: Rc4::new(start_key),
+            stateless,
+            // The first packet has a coherency count of 0.
+            ccount: MPPE_CCOUNT_MASK,
+        };
+
+        state.rekey(true);
+        state
+    }
+
+    fn rekey(&mut self, initial: bool) {
+        let len = self.strength.key_len();
+
+        let mut sha = Sha1::new();
+        sha.update(&self.start_key[..len]);
+        sha.update(SHS_PAD1);
+        sha.update(&self.session_key[..len]);
+        sha.update(SHS_PAD2);
+        self.session_key[..len].copy_from_slice(&sha.finalize()[..len]);
+
+        if !initial {
+            Rc4::new(&self.session_key[..len]).apply(&mut self.session_key[..len]);
+        }
+
+        match self.strength {
+            MppeStrength::Bits40 => self.session_key[..3].copy_from_slice(&[0xd1, 0x26, 0x9e]),
+            MppeStrength::Bits56 => self.session_key[0] = 0xd1,
+            MppeStrength::Bits128 => {}
+        }
+
+        self.rc4 = Rc4::new(&self.session_key[..len]);
+    }
+
+    fn next_ccount(&self) -> u16 {
+        self.ccount.wrapping_add(1) & MPPE_CCOUNT_MASK
+    }
+}
+
+impl std::fmt::Debug for MppeState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Don't leak key material into logs.
+        f.debug_struct("MppeState")
+            .field("strength", &self.strength)
+            .field("stateless", &self.stateless)
+            .field("ccount", &self.ccount)
+            .finish_non_exhaustive()
+    }
+}
+
+/// MPPE encryption as described in RFC 3078.
+#[derive(Debug)]
+pub struct MppeCompressor {
+    state: MppeState,
+    flush: bool,
+}
+
+impl MppeCompressor {
+    /// Creates an encrypting half using the send key of [`MppeKeys`].
+    pub fn new(send_key: &[u8; 16], strength: MppeStrength, stateless: bool) -> Self {
+        Self {
+            state: MppeState::new(send_key, strength, stateless),
+            flush: false,
+        }
+    }
+}
+
+impl Compressor for MppeCompressor {
+    fn compress(&mut self, pkt: &PppPkt) -> Result<PppPkt> {
+        if !is_encrypted(pkt.protocol()) {
+            return Ok(pkt.clone());
+        }
+
+        let state = &mut self.state;
+        state.ccount = state.next_ccount();
+
+        let mut header = MPPE_ENCRYPTED | state.ccount;
+        if state.stateless || state.ccount & 0xff == 0xff || self.flush {
+            state.rekey(false);
+            self.flush = false;
+            header |= MPPE_FLUSHED;
+        }
+
+        let mut data = header.to_be_bytes().to_vec();
+        pkt.serialize_framed(&mut data, &Framing::default())?;
+        state.rc4.apply(&mut data[2..]);
+
+        Ok(PppPkt::new_compressed(data))
+    }
+
+    /// Rekeys before the next packet, the peer resynchronizes using the flushed bit.
+    fn reset(&mut self) {
+        self.flush = true;
+    }
+}
+
+/// MPPE decryption as described in RFC 3078.
+#[derive(Debug)]
+pub struct MppeDecompressor {
+    state: MppeState,
+    discard: bool,
+}
+
+impl MppeDecompressor {
+    /// Creates a decrypting half using the receive key of [`MppeKeys`].
+    pub fn new(receive_key: &[u8; 16], strength: MppeStrength, stateless: bool) -> Self {
+        Self {
+            state: MppeState::new(receive_key, strength, stateless),
+            discard: false,
+        }
+    }
+
+    /// Advances the session key to the one `ccount` was encrypted with.
+    fn sync(&mut self, ccount: u16, flushed: bool) -> Result<()> {
+        let state = &mut self.state;
+
+        if state.stateless {
+            // Anything not ahead of the last coherency count is late or a duplicate.
+            let ahead = ccount.wrapping_sub(state.ccount) & MPPE_CCOUNT_MASK;
+            if ahead == 0 || ahead > MPPE_CCOUNT_MASK / 2 {
+                return Err(Error::UnexpectedCompressionSequence(ccount));
+            }
+
+            while state.ccount != ccount {
+                state.rekey(false);
+                state.ccount = state.next_ccount();
+            }
+
+            return Ok(());
+        }
+
+        if !self.discard {
+            state.ccount = state.next_ccount();
+            if ccount != state.ccount {
+                // Wait for the peer to flush after our Reset-Request.
+                self.discard = true;
+                return Err(Error::UnexpectedCompressionSequence(ccount));
+            }
+        } else if !flushed {
+            return Err(Error::UnexpectedCompressionSequence(ccount));
+        } else {
+            // Catch up on the rekeying of every missed flag packet.
+            while ccount & !0xff != state.ccount & !0xff {
+                state.rekey(false);
+                state.ccount = state.ccount.wrapping_add(0x100) & MPPE_CCOUNT_MASK;
+            }
+
+            self.discard = false;
+            state.ccount = ccount;
+        }
+
+        if flushed {
+            state.rekey(false);
+        }
+
+        Ok(())
+    }
+}
+
+impl Decompressor for MppeDecompressor {
+    fn decompress(&mut self, data: &[u8]) -> Result<PppPkt> {
+        if data.len() < 3 {
+            return Err(Error::CompressedDatagramTooShort(data.len()));
+        }
+
+        let header = u16::from_be_bytes([data[0], data[1]]);
+        if header & MPPE_ENCRYPTED == 0 || header & MPPE_COMPRESSED != 0 {
+            return Err(Error::InvalidMppeHeader(header));
+        }
+
+        self.sync(header & MPPE_CCOUNT_MASK, header & MPPE_FLUSHED != 0)?;
+
+        let mut payload = data[2..].to_vec();
+        self.state.rc4.apply(&mut payload);
+
+        let framing = Framing {
+            pfc: true,
+            ..Default::default()
+        };
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize_framed(&mut payload.as_slice(), &framing)?;
+
+        Ok(pkt)
+    }
+
+    fn accepts_uncompressed(&self, pkt: &PppPkt) -> bool {
+        !is_encrypted(pkt.protocol())
+    }
+
+    fn waits_for_reset_ack(&self) -> bool {
+        false
+    }
+
+    fn reset(&mut self) {}
+}
@@ -0,0 +1,666 @@
+use crate::{
+    ControlMsg, ControlPacket, Deserialize, Error, Result, Serialize, CODE_REJECT_HEADER_LEN,
+};
+
+use std::fmt;
+use std::io::{Read, Write};
+
+use ppproperly_macros::{Deserialize, Serialize};
+
+pub const ECP_CONFIGURE_REQUEST: u8 = 1;
+pub const ECP_CONFIGURE_ACK: u8 = 2;
+pub const ECP_CONFIGURE_NAK: u8 = 3;
+pub const ECP_CONFIGURE_REJECT: u8 = 4;
+pub const ECP_TERMINATE_REQUEST: u8 = 5;
+pub const ECP_TERMINATE_ACK: u8 = 6;
+pub const ECP_CODE_REJECT: u8 = 7;
+pub const ECP_RESET_REQUEST: u8 = 14;
+pub const ECP_RESET_ACK: u8 = 15;
+
+// The original DESE option (RFC 1969) is superseded by DESE-bis
+// and only available as an unhandled option.
+pub const OPT_TRIPLE_DESE: u8 = 2;
+pub const OPT_DESE_BIS: u8 = 3;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EcpOpt {
+    /// Triple-DES encryption as described in RFC 2420, carrying the Initial Nonce.
+    TripleDese([u8; 8]),
+    /// DES encryption as described in RFC 2419, carrying the Initial Nonce.
+    DeseBis([u8; 8]),
+    Unhandled(u8, Vec<u8>),
+}
+
+impl Serialize for EcpOpt {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Self::TripleDese(payload) => payload.serialize(w),
+            Self::DeseBis(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
+        }
+    }
+}
+
+impl EcpOpt {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::TripleDese(_) => OPT_TRIPLE_DESE,
+            Self::DeseBis(_) => OPT_DESE_BIS,
+            Self::Unhandled(ty, _) => *ty,
+        }
+    }
+
+    fn len(&self) -> u8 {
+        match self {
+            Self::TripleDese(_) => 8,
+            Self::DeseBis(_) => 8,
+            Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!(
+                    "unhandled ecp option {} length {} exceeds 255",
+                    *ty,
+                    payload.len()
+                )
+            }),
+        }
+    }
+
+    fn deserialize_with_discriminant<R: Read>(
+        &mut self,
+        r: &mut R,
+        discriminant: &u8,
+    ) -> Result<()> {
+        match *discriminant {
+            OPT_TRIPLE_DESE => {
+                let mut tmp = [0; 8];
+
+                r.read_exact(&mut tmp)?;
+                *self = Self::TripleDese(tmp);
+            }
+            OPT_DESE_BIS => {
+                let mut tmp = [0; 8];
+
+                r.read_exact(&mut tmp)?;
+                *self = Self::DeseBis(tmp);
+            }
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(*discriminant, tmp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpOption {
+    #[ppproperly(discriminant_for(field = "value", data_type = "u8"))]
+    #[ppproperly(len_for(field = "value", offset = 2, data_type = "u8"))]
+    pub value: EcpOpt,
+}
+
+impl EcpOption {
+    pub fn len(&self) -> u8 {
+        2 + self.value.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 2
+    }
+}
+
+impl From<EcpOpt> for EcpOption {
+    fn from(value: EcpOpt) -> Self {
+        Self { value }
+    }
+}
+
+impl Serialize for [EcpOption] {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        for option in self {
+            option.serialize(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Deserialize for Vec<EcpOption> {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+
+        let mut r = buf.as_slice();
+        while !r.is_empty() {
+            let mut tmp = EcpOption::from(EcpOpt::DeseBis([0; 8]));
+
+            tmp.deserialize(&mut r)?;
+            self.push(tmp);
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EcpData {
+    ConfigureRequest(EcpConfigureRequest),
+    ConfigureAck(EcpConfigureAck),
+    ConfigureNak(EcpConfigureNak),
+    ConfigureReject(EcpConfigureReject),
+    TerminateRequest(EcpTerminateRequest),
+    TerminateAck(EcpTerminateAck),
+    CodeReject(EcpCodeReject),
+    ResetRequest(EcpResetRequest),
+    ResetAck(EcpResetAck),
+    Unhandled(u8, Vec<u8>),
+}
+
+impl Default for EcpData {
+    fn default() -> Self {
+        Self::ConfigureRequest(EcpConfigureRequest::default())
+    }
+}
+
+impl Serialize for EcpData {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Self::ConfigureRequest(payload) => payload.serialize(w),
+            Self::ConfigureAck(payload) => payload.serialize(w),
+            Self::ConfigureNak(payload) => payload.serialize(w),
+            Self::ConfigureReject(payload) => payload.serialize(w),
+            Self::TerminateRequest(payload) => payload.serialize(w),
+            Self::TerminateAck(payload) => payload.serialize(w),
+            Self::CodeReject(payload) => payload.serialize(w),
+            Self::ResetRequest(payload) => payload.serialize(w),
+            Self::ResetAck(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
+        }
+    }
+}
+
+impl EcpData {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::ConfigureRequest(_) => ECP_CONFIGURE_REQUEST,
+            Self::ConfigureAck(_) => ECP_CONFIGURE_ACK,
+            Self::ConfigureNak(_) => ECP_CONFIGURE_NAK,
+            Self::ConfigureReject(_) => ECP_CONFIGURE_REJECT,
+            Self::TerminateRequest(_) => ECP_TERMINATE_REQUEST,
+            Self::TerminateAck(_) => ECP_TERMINATE_ACK,
+            Self::CodeReject(_) => ECP_CODE_REJECT,
+            Self::ResetRequest(_) => ECP_RESET_REQUEST,
+            Self::ResetAck(_) => ECP_RESET_ACK,
+            Self::Unhandled(ty, _) => *ty,
+        }
+    }
+
+    fn len(&self) -> u16 {
+        match self {
+            Self::ConfigureRequest(payload) => payload.len(),
+            Self::ConfigureAck(payload) => payload.len(),
+            Self::ConfigureNak(payload) => payload.len(),
+            Self::ConfigureReject(payload) => payload.len(),
+            Self::TerminateRequest(payload) => payload.len(),
+            Self::TerminateAck(payload) => payload.len(),
+            Self::CodeReject(payload) => payload.len(),
+            Self::ResetRequest(payload) => payload.len(),
+            Self::ResetAck(payload) => payload.len(),
+            Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!(
+                    "unhandled ecp code {} length {} exceeds 65535",
+                    *ty,
+                    payload.len()
+                )
+            }),
+        }
+    }
+
+    fn deserialize_with_discriminant<R: Read>(
+        &mut self,
+        r: &mut R,
+        discriminant: &u8,
+    ) -> Result<()> {
+        match *discriminant {
+            ECP_CONFIGURE_REQUEST => {
+                let mut tmp = EcpConfigureRequest::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureRequest(tmp);
+            }
+            ECP_CONFIGURE_ACK => {
+                let mut tmp = EcpConfigureAck::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureAck(tmp);
+            }
+            ECP_CONFIGURE_NAK => {
+                let mut tmp = EcpConfigureNak::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureNak(tmp);
+            }
+            ECP_CONFIGURE_REJECT => {
+                let mut tmp = EcpConfigureReject::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ConfigureReject(tmp);
+            }
+            ECP_TERMINATE_REQUEST => {
+                let mut tmp = EcpTerminateRequest::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::TerminateRequest(tmp);
+            }
+            ECP_TERMINATE_ACK => {
+                let mut tmp = EcpTerminateAck::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::TerminateAck(tmp);
+            }
+            ECP_CODE_REJECT => {
+                let mut tmp = EcpCodeReject::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::CodeReject(tmp);
+            }
+            ECP_RESET_REQUEST => {
+                let mut tmp = EcpResetRequest::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ResetRequest(tmp);
+            }
+            ECP_RESET_ACK => {
+                let mut tmp = EcpResetAck::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ResetAck(tmp);
+            }
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(*discriminant, tmp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpPkt {
+    #[ppproperly(discriminant_for(field = "data", data_type = "u8"))]
+    pub identifier: u8,
+    #[ppproperly(len_for(field = "data", offset = 4, data_type = "u16"))]
+    pub data: EcpData,
+}
+
+impl EcpPkt {
+    pub fn new_configure_request(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::ConfigureRequest(EcpConfigureRequest { options }),
+        }
+    }
+
+    pub fn new_configure_ack(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::ConfigureAck(EcpConfigureAck { options }),
+        }
+    }
+
+    pub fn new_configure_nak(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::ConfigureNak(EcpConfigureNak { options }),
+        }
+    }
+
+    pub fn new_configure_reject(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::ConfigureReject(EcpConfigureReject { options }),
+        }
+    }
+
+    pub fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::TerminateRequest(EcpTerminateRequest { data }),
+        }
+    }
+
+    pub fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::TerminateAck(EcpTerminateAck { data }),
+        }
+    }
+
+    pub fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::CodeReject(EcpCodeReject { pkt }),
+        }
+    }
+
+    pub fn new_reset_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::ResetRequest(EcpResetRequest { data }),
+        }
+    }
+
+    pub fn new_reset_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: EcpData::ResetAck(EcpResetAck { data }),
+        }
+    }
+
+    /// Builds a Code-Reject of `rejected`, truncated to fit the peer's `mru`.
+    pub fn new_code_reject_for(identifier: u8, rejected: &EcpPkt, mru: u16) -> Result<Self> {
+        let mut pkt = Vec::new();
+        rejected.serialize(&mut pkt)?;
+
+        pkt.truncate(usize::from(mru).saturating_sub(CODE_REJECT_HEADER_LEN));
+        Ok(Self::new_code_reject(identifier, pkt))
+    }
+
+    pub fn len(&self) -> u16 {
+        4 + self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 4
+    }
+}
+
+impl ControlPacket for EcpPkt {
+    type Option = EcpOption;
+
+    fn new_configure_request(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self::new_configure_request(identifier, options)
+    }
+
+    fn new_configure_ack(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self::new_configure_ack(identifier, options)
+    }
+
+    fn new_configure_nak(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self::new_configure_nak(identifier, options)
+    }
+
+    fn new_configure_reject(identifier: u8, options: Vec<EcpOption>) -> Self {
+        Self::new_configure_reject(identifier, options)
+    }
+
+    fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_request(identifier, data)
+    }
+
+    fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_ack(identifier, data)
+    }
+
+    fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self::new_code_reject(identifier, pkt)
+    }
+
+    fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    fn msg(&self) -> ControlMsg<'_, EcpOption> {
+        match &self.data {
+            EcpData::ConfigureRequest(payload) => ControlMsg::ConfigureRequest(&payload.options),
+            EcpData::ConfigureAck(payload) => ControlMsg::ConfigureAck(&payload.options),
+            EcpData::ConfigureNak(payload) => ControlMsg::ConfigureNak(&payload.options),
+            EcpData::ConfigureReject(payload) => ControlMsg::ConfigureReject(&payload.options),
+            EcpData::TerminateRequest(_) => ControlMsg::TerminateRequest,
+            EcpData::TerminateAck(_) => ControlMsg::TerminateAck,
+            EcpData::CodeReject(payload) => ControlMsg::CodeReject(&payload.pkt),
+            EcpData::ResetRequest(_) | EcpData::ResetAck(_) => ControlMsg::Other,
+            EcpData::Unhandled(..) => ControlMsg::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for EcpPkt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ECP id={}: ", self.identifier)?;
+        match &self.data {
+            EcpData::ConfigureRequest(cfg_req) => cfg_req.fmt(f),
+            EcpData::ConfigureAck(cfg_ack) => cfg_ack.fmt(f),
+            EcpData::ConfigureNak(cfg_nak) => cfg_nak.fmt(f),
+            EcpData::ConfigureReject(cfg_rej) => cfg_rej.fmt(f),
+            EcpData::TerminateRequest(term_req) => term_req.fmt(f),
+            EcpData::TerminateAck(term_ack) => term_ack.fmt(f),
+            EcpData::CodeReject(code_rej) => code_rej.fmt(f),
+            EcpData::ResetRequest(reset_req) => reset_req.fmt(f),
+            EcpData::ResetAck(reset_ack) => reset_ack.fmt(f),
+            EcpData::Unhandled(ty, payload) => write!(f, "uc={} {:?}", ty, payload),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpConfigureRequest {
+    pub options: Vec<EcpOption>,
+}
+
+impl EcpConfigureRequest {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for EcpConfigureRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cfg-Req {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpConfigureAck {
+    pub options: Vec<EcpOption>,
+}
+
+impl EcpConfigureAck {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for EcpConfigureAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cfg-Ack {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpConfigureNak {
+    pub options: Vec<EcpOption>,
+}
+
+impl EcpConfigureNak {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for EcpConfigureNak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Cfg-Nak {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpConfigureReject {
+    pub options: Vec<EcpOption>,
+}
+
+impl EcpConfigureReject {
+    pub fn len(&self) -> u16 {
+        self.options
+            .iter()
+            .map(|option| option.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+}
+
+impl fmt::Display for EcpConfigureReject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cfg-Rej {:?}", self.options)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpTerminateRequest {
+    pub data: Vec<u8>,
+}
+
+impl EcpTerminateRequest {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for EcpTerminateRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Term-Req {}",
+            std::str::from_utf8(&self.data).unwrap_or(&format!("{:?}", self.data))
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpTerminateAck {
+    pub data: Vec<u8>,
+}
+
+impl EcpTerminateAck {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for EcpTerminateAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Term-Ack {}",
+            std::str::from_utf8(&self.data).unwrap_or(&format!("{:?}", self.data))
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpCodeReject {
+    pub pkt: Vec<u8>, // Vec makes MRU truncating easier without overwriting (de)ser impls.
+}
+
+impl EcpCodeReject {
+    pub fn len(&self) -> u16 {
+        self.pkt.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pkt.is_empty()
+    }
+}
+
+impl fmt::Display for EcpCodeReject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Code-Rej {:?}", self.pkt)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpResetRequest {
+    pub data: Vec<u8>,
+}
+
+impl EcpResetRequest {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for EcpResetRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reset-Req {:?}", self.data)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EcpResetAck {
+    pub data: Vec<u8>,
+}
+
+impl EcpResetAck {
+    pub fn len(&self) -> u16 {
+        self.data.len().try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl fmt::Display for EcpResetAck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Reset-Ack {:?}", self.data)
+    }
+}
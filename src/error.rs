@@ -11,7 +11,31 @@ pub enum Error {
     InvalidPppoeCode(u8),
     #[error("invalid pppoe tag: {0}")]
     InvalidPppoeTag(u16),
+    #[error("missing ppp address and control fields")]
+    MissingAddrCtl,
+    #[error("unexpected compressed ppp protocol: {0}")]
+    UnexpectedPfc(u8),
+    #[error("invalid hdlc fcs")]
+    InvalidFcs,
+    #[error("hdlc frame too long")]
+    HdlcFrameTooLong,
+    #[error("hdlc frame too short: {0}")]
+    HdlcFrameTooShort(usize),
+    #[error("multilink bundle has no usable member links")]
+    EmptyBundle,
+    #[error("compressed datagram too short: {0}")]
+    CompressedDatagramTooShort(usize),
+    #[error("unexpected compressed datagram sequence number: {0}")]
+    UnexpectedCompressionSequence(u16),
+    #[error("decompressed packet too long")]
+    DecompressedTooLong,
+    #[error("invalid mppe header: {0:#06x}")]
+    InvalidMppeHeader(u16),
 
+    #[error("deflate compression: {0}")]
+    Compress(#[from] flate2::CompressError),
+    #[error("deflate decompression: {0}")]
+    Decompress(#[from] flate2::DecompressError),
     #[error("conversion from utf8: {0}")]
     FromUtf8(#[from] string::FromUtf8Error),
     #[error("io: {0}")]
@@ -0,0 +1,599 @@
+use crate::{Serialize, DEFAULT_MRU};
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Highest code the option negotiation automaton itself relies on.
+/// A Code-Reject for any of these is fatal (RXJ-).
+const MAX_ESSENTIAL_CODE: u8 = 7;
+
+/// Length of the Code, Identifier and Length fields preceding
+/// the rejected packet in a Code-Reject.
+pub const CODE_REJECT_HEADER_LEN: usize = 4;
+
+/// The RFC 1661 automaton states.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FsmState {
+    Initial,
+    Starting,
+    Closed,
+    Stopped,
+    Closing,
+    Stopping,
+    ReqSent,
+    AckRcvd,
+    AckSent,
+    Opened,
+}
+
+/// Layer actions emitted by an [`Fsm`] for the layers around it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FsmAction {
+    /// The automaton has entered the Opened state.
+    ThisLayerUp,
+    /// The automaton is leaving the Opened state.
+    ThisLayerDown,
+    /// The lower layer is needed, signal an Up event once it is available.
+    ThisLayerStarted,
+    /// The lower layer is no longer needed.
+    ThisLayerFinished,
+}
+
+/// Peer Configure-Request verdict of a [`ControlProtocol`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FsmVerdict<O> {
+    Ack,
+    /// Options with acceptable values the peer should request instead.
+    Nak(Vec<O>),
+    /// Options that aren't negotiable at all.
+    Reject(Vec<O>),
+}
+
+/// Received packet as seen by the automaton.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ControlMsg<'a, O> {
+    ConfigureRequest(&'a [O]),
+    ConfigureAck(&'a [O]),
+    ConfigureNak(&'a [O]),
+    ConfigureReject(&'a [O]),
+    TerminateRequest,
+    TerminateAck,
+    CodeReject(&'a [u8]),
+    /// Known code that isn't part of option negotiation, e.g. Echo-Request.
+    /// The automaton ignores it.
+    Other,
+    /// Unknown code, answered with a Code-Reject.
+    Unknown,
+}
+
+/// Packet format of a control protocol using the RFC 1661 option negotiation.
+pub trait ControlPacket: Serialize + Sized {
+    type Option: Clone;
+
+    fn new_configure_request(identifier: u8, options: Vec<Self::Option>) -> Self;
+    fn new_configure_ack(identifier: u8, options: Vec<Self::Option>) -> Self;
+    fn new_configure_nak(identifier: u8, options: Vec<Self::Option>) -> Self;
+    fn new_configure_reject(identifier: u8, options: Vec<Self::Option>) -> Self;
+    fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self;
+    fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self;
+    fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self;
+
+    fn identifier(&self) -> u8;
+    fn msg(&self) -> ControlMsg<'_, Self::Option>;
+}
+
+/// Option policy of a control protocol driven by an [`Fsm`].
+pub trait ControlProtocol {
+    type Packet: ControlPacket;
+
+    /// Returns the options to send in the next Configure-Request.
+    fn request_options(&mut self) -> Vec<<Self::Packet as ControlPacket>::Option>;
+
+    /// Decides whether the options requested by the peer are acceptable.
+    fn check_request(
+        &mut self,
+        options: &[<Self::Packet as ControlPacket>::Option],
+    ) -> FsmVerdict<<Self::Packet as ControlPacket>::Option>;
+
+    /// Called when the peer has acknowledged our Configure-Request.
+    fn handle_ack(&mut self, _options: &[<Self::Packet as ControlPacket>::Option]) {}
+
+    /// Called when the peer has sent a Configure-Nak for our Configure-Request.
+    fn handle_nak(&mut self, _options: &[<Self::Packet as ControlPacket>::Option]) {}
+
+    /// Called when the peer has sent a Configure-Reject for our Configure-Request.
+    fn handle_reject(&mut self, _options: &[<Self::Packet as ControlPacket>::Option]) {}
+}
+
+/// Settings of an [`Fsm`], defaults as suggested by RFC 1661.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FsmConfig {
+    /// Retransmission interval of Configure-Requests and Terminate-Requests.
+    pub restart_timer: Duration,
+    /// Number of Terminate-Requests sent without an answer before giving up.
+    pub max_terminate: u32,
+    /// Number of Configure-Requests sent without an answer before giving up.
+    pub max_configure: u32,
+    /// Number of Configure-Naks sent before they are turned into Configure-Rejects.
+    pub max_failure: u32,
+}
+
+impl Default for FsmConfig {
+    fn default() -> Self {
+        Self {
+            restart_timer: Duration::from_secs(3),
+            max_terminate: 2,
+            max_configure: 10,
+            max_failure: 5,
+        }
+    }
+}
+
+/// Sans-IO implementation of the RFC 1661 option negotiation automaton.
+///
+/// Signal lower layer changes using [`Fsm::up`] and [`Fsm::down`]
+/// and administrative changes using [`Fsm::open`] and [`Fsm::close`].
+/// Feed received packets into [`Fsm::handle_packet`] and call
+/// [`Fsm::handle_timeout`] once [`Fsm::poll_timeout`] has passed.
+/// Packets to send are returned by [`Fsm::poll_transmit`],
+/// layer actions by [`Fsm::poll_action`].
+#[derive(Clone, Debug)]
+pub struct Fsm<P: ControlProtocol> {
+    protocol: P,
+    config: FsmConfig,
+    state: FsmState,
+    restart_counter: u32,
+    failure_counter: u32,
+    deadline: Option<Instant>,
+    next_identifier: u8,
+    request_identifier: u8,
+    peer_mru: u16,
+    transmit: VecDeque<P::Packet>,
+    actions: VecDeque<FsmAction>,
+}
+
+impl<P: ControlProtocol> Fsm<P> {
+    pub fn new(protocol: P, config: FsmConfig) -> Self {
+        Self {
+            protocol,
+            config,
+            state: FsmState::Initial,
+            restart_counter: 0,
+            failure_counter: 0,
+            deadline: None,
+            next_identifier: 0,
+            request_identifier: 0,
+            peer_mru: DEFAULT_MRU,
+            transmit: VecDeque::new(),
+            actions: VecDeque::new(),
+        }
+    }
+
+    pub fn state(&self) -> FsmState {
+        self.state
+    }
+
+    pub fn is_opened(&self) -> bool {
+        self.state == FsmState::Opened
+    }
+
+    pub fn protocol(&self) -> &P {
+        &self.protocol
+    }
+
+    pub fn protocol_mut(&mut self) -> &mut P {
+        &mut self.protocol
+    }
+
+    /// Sets the MRU negotiated by the peer. Code-Rejects are truncated to fit.
+    pub fn set_peer_mru(&mut self, mru: u16) {
+        self.peer_mru = mru;
+    }
+
+    /// Signals that the lower layer is ready to carry packets.
+    pub fn up(&mut self, now: Instant) {
+        match self.state {
+            FsmState::Initial => self.state = FsmState::Closed,
+            FsmState::Starting => {
+                self.irc_configure();
+                self.scr(now);
+                self.state = FsmState::ReqSent;
+            }
+            _ => {}
+        }
+    }
+
+    /// Signals that the lower layer is no longer ready to carry packets.
+    pub fn down(&mut self) {
+        self.state = match self.state {
+            FsmState::Closed | FsmState::Closing => FsmState::Initial,
+            FsmState::Stopped => {
+                self.actions.push_back(FsmAction::ThisLayerStarted);
+                FsmState::Starting
+            }
+            FsmState::Stopping | FsmState::ReqSent | FsmState::AckRcvd | FsmState::AckSent => {
+                FsmState::Starting
+            }
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                FsmState::Starting
+            }
+            state => state,
+        };
+    }
+
+    /// Administratively allows the link to be opened.
+    pub fn open(&mut self, now: Instant) {
+        self.state = match self.state {
+            FsmState::Initial => {
+                self.actions.push_back(FsmAction::ThisLayerStarted);
+                FsmState::Starting
+            }
+            FsmState::Closed => {
+                self.irc_configure();
+                self.scr(now);
+                FsmState::ReqSent
+            }
+            FsmState::Closing => FsmState::Stopping,
+            state => state,
+        };
+    }
+
+    /// Administratively closes the link.
+    pub fn close(&mut self, now: Instant) {
+        self.state = match self.state {
+            FsmState::Starting => {
+                self.actions.push_back(FsmAction::ThisLayerFinished);
+                FsmState::Initial
+            }
+            FsmState::Stopped => FsmState::Closed,
+            FsmState::Stopping => FsmState::Closing,
+            FsmState::ReqSent | FsmState::AckRcvd | FsmState::AckSent => {
+                self.irc_terminate();
+                self.str(now);
+                FsmState::Closing
+            }
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                self.irc_terminate();
+                self.str(now);
+                FsmState::Closing
+            }
+            state => state,
+        };
+    }
+
+    pub fn handle_packet(&mut self, pkt: &P::Packet, now: Instant) {
+        // Nothing can be received before the lower layer is up.
+        if matches!(self.state, FsmState::Initial | FsmState::Starting) {
+            return;
+        }
+
+        match pkt.msg() {
+            ControlMsg::ConfigureRequest(options) => self.rcr(pkt.identifier(), options, now),
+            ControlMsg::ConfigureAck(options) => {
+                if pkt.identifier() == self.request_identifier {
+                    self.rca(options, now);
+                }
+            }
+            ControlMsg::ConfigureNak(options) => {
+                if pkt.identifier() == self.request_identifier {
+                    self.protocol.handle_nak(options);
+                    self.rcn(now);
+                }
+            }
+            ControlMsg::ConfigureReject(options) => {
+                if pkt.identifier() == self.request_identifier {
+                    self.protocol.handle_reject(options);
+                    self.rcn(now);
+                }
+            }
+            ControlMsg::TerminateRequest => self.rtr(pkt.identifier(), now),
+            ControlMsg::TerminateAck => self.rta(now),
+            ControlMsg::CodeReject(rejected) => match rejected.first() {
+                Some(code) if (1..=MAX_ESSENTIAL_CODE).contains(code) => self.rxj_bad(now),
+                _ => self.rxj_good(),
+            },
+            ControlMsg::Other => {}
+            ControlMsg::Unknown => self.ruc(pkt),
+        }
+    }
+
+    pub fn handle_timeout(&mut self, now: Instant) {
+        match self.poll_timeout() {
+            Some(deadline) if deadline <= now => {}
+            _ => return,
+        }
+
+        if self.restart_counter > 0 {
+            // TO+
+            match self.state {
+                FsmState::Closing | FsmState::Stopping => self.str(now),
+                FsmState::ReqSent | FsmState::AckRcvd => {
+                    self.scr(now);
+                    self.state = FsmState::ReqSent;
+                }
+                FsmState::AckSent => self.scr(now),
+                _ => {}
+            }
+        } else {
+            // TO-
+            self.state = match self.state {
+                FsmState::Closing => FsmState::Closed,
+                _ => FsmState::Stopped,
+            };
+
+            self.deadline = None;
+            self.actions.push_back(FsmAction::ThisLayerFinished);
+        }
+    }
+
+    /// Returns the point in time at which [`Fsm::handle_timeout`] needs to be called.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        match self.state {
+            FsmState::Closing
+            | FsmState::Stopping
+            | FsmState::ReqSent
+            | FsmState::AckRcvd
+            | FsmState::AckSent => self.deadline,
+            _ => None,
+        }
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<P::Packet> {
+        self.transmit.pop_front()
+    }
+
+    pub fn poll_action(&mut self) -> Option<FsmAction> {
+        self.actions.pop_front()
+    }
+
+    /// Returns a fresh identifier for protocol specific requests
+    /// such as Echo-Requests.
+    pub fn next_identifier(&mut self) -> u8 {
+        let identifier = self.next_identifier;
+        self.next_identifier = self.next_identifier.wrapping_add(1);
+
+        identifier
+    }
+
+    fn rcr(
+        &mut self,
+        identifier: u8,
+        options: &[<P::Packet as ControlPacket>::Option],
+        now: Instant,
+    ) {
+        match self.state {
+            FsmState::Closed => {
+                self.sta(identifier);
+                return;
+            }
+            FsmState::Closing | FsmState::Stopping => return,
+            _ => {}
+        }
+
+        let verdict = self.protocol.check_request(options);
+        let good = matches!(verdict, FsmVerdict::Ack);
+
+        match self.state {
+            FsmState::Stopped => {
+                self.irc_configure();
+                self.scr(now);
+            }
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                self.scr(now);
+            }
+            _ => {}
+        }
+
+        match verdict {
+            FsmVerdict::Ack => self.sca(identifier, options.to_vec()),
+            FsmVerdict::Nak(options) => self.scn(identifier, options),
+            FsmVerdict::Reject(options) => {
+                self.transmit
+                    .push_back(P::Packet::new_configure_reject(identifier, options));
+            }
+        }
+
+        self.state = match (self.state, good) {
+            (FsmState::AckRcvd, true) => {
+                self.actions.push_back(FsmAction::ThisLayerUp);
+                FsmState::Opened
+            }
+            (FsmState::AckRcvd, false) => FsmState::AckRcvd,
+            (_, true) => FsmState::AckSent,
+            (_, false) => FsmState::ReqSent,
+        };
+    }
+
+    fn rca(&mut self, options: &[<P::Packet as ControlPacket>::Option], now: Instant) {
+        match self.state {
+            FsmState::Closed | FsmState::Stopped => {
+                let identifier = self.next_identifier();
+                self.sta(identifier);
+            }
+            FsmState::ReqSent => {
+                self.protocol.handle_ack(options);
+                self.irc_configure();
+                self.state = FsmState::AckRcvd;
+            }
+            FsmState::AckRcvd => {
+                // Crossed connection.
+                self.scr(now);
+                self.state = FsmState::ReqSent;
+            }
+            FsmState::AckSent => {
+                self.protocol.handle_ack(options);
+                self.irc_configure();
+                self.deadline = None;
+                self.actions.push_back(FsmAction::ThisLayerUp);
+                self.state = FsmState::Opened;
+            }
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                self.scr(now);
+                self.state = FsmState::ReqSent;
+            }
+            _ => {}
+        }
+    }
+
+    fn rcn(&mut self, now: Instant) {
+        match self.state {
+            FsmState::Closed | FsmState::Stopped => {
+                let identifier = self.next_identifier();
+                self.sta(identifier);
+            }
+            FsmState::ReqSent | FsmState::AckSent => {
+                self.irc_configure();
+                self.scr(now);
+            }
+            FsmState::AckRcvd => {
+                self.scr(now);
+                self.state = FsmState::ReqSent;
+            }
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                self.scr(now);
+                self.state = FsmState::ReqSent;
+            }
+            _ => {}
+        }
+    }
+
+    fn rtr(&mut self, identifier: u8, now: Instant) {
+        self.sta(identifier);
+
+        self.state = match self.state {
+            FsmState::AckRcvd | FsmState::AckSent => FsmState::ReqSent,
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                // Zero-Restart-Count: wait one restart period before finishing.
+                self.restart_counter = 0;
+                self.deadline = Some(now + self.config.restart_timer);
+
+                FsmState::Stopping
+            }
+            state => state,
+        };
+    }
+
+    fn rta(&mut self, now: Instant) {
+        self.state = match self.state {
+            FsmState::Closing => {
+                self.actions.push_back(FsmAction::ThisLayerFinished);
+                FsmState::Closed
+            }
+            FsmState::Stopping => {
+                self.actions.push_back(FsmAction::ThisLayerFinished);
+                FsmState::Stopped
+            }
+            FsmState::AckRcvd => FsmState::ReqSent,
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                self.scr(now);
+                FsmState::ReqSent
+            }
+            state => state,
+        };
+    }
+
+    fn ruc(&mut self, pkt: &P::Packet) {
+        let mut buf = Vec::new();
+        if pkt.serialize(&mut buf).is_err() {
+            return;
+        }
+
+        buf.truncate(usize::from(self.peer_mru).saturating_sub(CODE_REJECT_HEADER_LEN));
+
+        let identifier = self.next_identifier();
+        self.transmit
+            .push_back(P::Packet::new_code_reject(identifier, buf));
+    }
+
+    fn rxj_good(&mut self) {
+        if self.state == FsmState::AckRcvd {
+            self.state = FsmState::ReqSent;
+        }
+    }
+
+    fn rxj_bad(&mut self, now: Instant) {
+        self.state = match self.state {
+            FsmState::Closed | FsmState::Closing => {
+                self.actions.push_back(FsmAction::ThisLayerFinished);
+                FsmState::Closed
+            }
+            FsmState::Stopped
+            | FsmState::Stopping
+            | FsmState::ReqSent
+            | FsmState::AckRcvd
+            | FsmState::AckSent => {
+                self.actions.push_back(FsmAction::ThisLayerFinished);
+                FsmState::Stopped
+            }
+            FsmState::Opened => {
+                self.actions.push_back(FsmAction::ThisLayerDown);
+                self.irc_terminate();
+                self.str(now);
+                FsmState::Stopping
+            }
+            state => state,
+        };
+    }
+
+    fn irc_configure(&mut self) {
+        self.restart_counter = self.config.max_configure;
+        self.failure_counter = self.config.max_failure;
+    }
+
+    fn irc_terminate(&mut self) {
+        self.restart_counter = self.config.max_terminate;
+    }
+
+    fn scr(&mut self, now: Instant) {
+        self.restart_counter = self.restart_counter.saturating_sub(1);
+        self.deadline = Some(now + self.config.restart_timer);
+
+        self.request_identifier = self.next_identifier();
+        let options = self.protocol.request_options();
+
+        self.transmit.push_back(P::Packet::new_configure_request(
+            self.request_identifier,
+            options,
+        ));
+    }
+
+    fn sca(&mut self, identifier: u8, options: Vec<<P::Packet as ControlPacket>::Option>) {
+        self.failure_counter = self.config.max_failure;
+        self.transmit
+            .push_back(P::Packet::new_configure_ack(identifier, options));
+    }
+
+    fn scn(&mut self, identifier: u8, options: Vec<<P::Packet as ControlPacket>::Option>) {
+        // Negotiation isn't converging, stop suggesting values.
+        if self.failure_counter == 0 {
+            self.transmit
+                .push_back(P::Packet::new_configure_reject(identifier, options));
+            return;
+        }
+
+        self.failure_counter -= 1;
+        self.transmit
+            .push_back(P::Packet::new_configure_nak(identifier, options));
+    }
+
+    fn str(&mut self, now: Instant) {
+        self.restart_counter = self.restart_counter.saturating_sub(1);
+        self.deadline = Some(now + self.config.restart_timer);
+
+        let identifier = self.next_identifier();
+        self.transmit
+            .push_back(P::Packet::new_terminate_request(identifier, Vec::new()));
+    }
+
+    fn sta(&mut self, identifier: u8) {
+        self.transmit
+            .push_back(P::Packet::new_terminate_ack(identifier, Vec::new()));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{Error, Framing, PppPkt, Result, DEFAULT_MRU, LCP};
+
+use std::collections::VecDeque;
+
+pub const HDLC_FLAG: u8 = 0x7e;
+pub const HDLC_ESCAPE: u8 = 0x7d;
+pub const HDLC_ESCAPE_XOR: u8 = 0x20;
+
+/// ACCM both directions start with, all control characters are escaped.
+pub const DEFAULT_ACCM: u32 = 0xffff_ffff;
+
+const FCS16_GOOD: u16 = 0xf0b8;
+const FCS32_GOOD: u32 = 0xdebb_20e3;
+
+/// Frame Check Sequence algorithm as described in RFC 1662 appendix C.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Fcs {
+    #[default]
+    Fcs16,
+    Fcs32,
+}
+
+impl Fcs {
+    /// Returns the number of bytes the FCS occupies.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Fcs16 => 2,
+            Self::Fcs32 => 4,
+        }
+    }
+
+    fn append(&self, buf: &mut Vec<u8>) {
+        match self {
+            Self::Fcs16 => buf.extend_from_slice(&fcs16(buf).to_le_bytes()),
+            Self::Fcs32 => buf.extend_from_slice(&fcs32(buf).to_le_bytes()),
+        }
+    }
+
+    fn check(&self, frame: &[u8]) -> bool {
+        match self {
+            Self::Fcs16 => fcs16_update(0xffff, frame) == FCS16_GOOD,
+            Self::Fcs32 => fcs32_update(0xffff_ffff, frame) == FCS32_GOOD,
+        }
+    }
+}
+
+/// Returns the 16-bit FCS of `data` in the order it is to be transmitted
+/// when converted using `to_le_bytes`.
+pub fn fcs16(data: &[u8]) -> u16 {
+    !fcs16_update(0xffff, data)
+}
+
+/// Returns the 32-bit FCS of `data` in the order it is to be transmitted
+/// when converted using `to_le_bytes`.
+pub fn fcs32(data: &[u8]) -> u32 {
+    !fcs32_update(0xffff_ffff, data)
+}
+
+fn fcs16_update(mut fcs: u16, data: &[u8]) -> u16 {
+    for byte in data {
+        fcs ^= u16::from(*byte);
+        for _ in 0..8 {
+            fcs = if fcs & 1 == 1 {
+                (fcs >> 1) ^ 0x8408
+            } else {
+                fcs >> 1
+            };
+        }
+    }
+
+    fcs
+}
+
+fn fcs32_update(mut fcs: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        fcs ^= u32::from(*byte);
+        for _ in 0..8 {
+            fcs = if fcs & 1 == 1 {
+                (fcs >> 1) ^ 0xedb8_8320
+            } else {
+                fcs >> 1
+            };
+        }
+    }
+
+    fcs
+}
+
+/// Settings of one direction of an HDLC-like link.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HdlcConfig {
+    /// Async-Control-Character-Map. Bit n set means character n is escaped
+    /// when sending and ignored if received unescaped.
+    pub accm: u32,
+    pub fcs: Fcs,
+    /// Header compression, see [`Framing`].
+    pub framing: Framing,
+    /// Longest frame accepted by the decoder, FCS included.
+    pub max_frame_len: usize,
+}
+
+impl Default for HdlcConfig {
+    fn default() -> Self {
+        Self {
+            accm: DEFAULT_ACCM,
+            fcs: Fcs::default(),
+            framing: Framing::hdlc(),
+            // Address, Control, Protocol and FCS-32 around the default MRU.
+            max_frame_len: usize::from(DEFAULT_MRU) + 8,
+        }
+    }
+}
+
+/// Wraps [`PppPkt`]s in RFC 1662 HDLC-like frames for asynchronous links.
+#[derive(Clone, Debug, Default)]
+pub struct HdlcEncoder {
+    config: HdlcConfig,
+}
+
+impl HdlcEncoder {
+    pub fn new(config: HdlcConfig) -> Self {
+        Self { config }
+    }
+
+    /// Sets the ACCM the peer negotiated.
+    pub fn set_accm(&mut self, accm: u32) {
+        self.config.accm = accm;
+    }
+
+    pub fn set_fcs(&mut self, fcs: Fcs) {
+        self.config.fcs = fcs;
+    }
+
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.config.framing = framing;
+    }
+
+    /// Returns the packet as a complete frame including opening and closing flags.
+    ///
+    /// LCP packets are always sent using the default ACCM
+    /// so that they survive a mismatch between both ends.
+    pub fn encode(&self, pkt: &PppPkt) -> Result<Vec<u8>> {
+        let mut frame = Vec::new();
+        pkt.serialize_framed(&mut frame, &self.config.framing)?;
+        self.config.fcs.append(&mut frame);
+
+        let accm = if pkt.protocol() == LCP {
+            DEFAULT_ACCM
+        } else {
+            self.config.accm
+        };
+
+        let mut buf = Vec::with_capacity(frame.len() + frame.len() / 4 + 2);
+        buf.push(HDLC_FLAG);
+
+        for byte in frame {
+            if needs_escape(byte, accm) {
+                buf.push(HDLC_ESCAPE);
+                buf.push(byte ^ HDLC_ESCAPE_XOR);
+            } else {
+                buf.push(byte);
+            }
+        }
+
+        buf.push(HDLC_FLAG);
+        Ok(buf)
+    }
+}
+
+fn needs_escape(byte: u8, accm: u32) -> bool {
+    match byte {
+        HDLC_FLAG | HDLC_ESCAPE => true,
+        0..=0x1f => accm & (1 << byte) != 0,
+        _ => false,
+    }
+}
+
+/// Streaming decoder of RFC 1662 HDLC-like frames.
+///
+/// Feed received bytes into [`HdlcDecoder::handle_bytes`] in chunks of any size,
+/// complete frames are returned by [`HdlcDecoder::poll_packet`].
+/// Empty and aborted frames are dropped silently, frames that are too long,
+/// too short or fail the FCS check are returned as errors.
+#[derive(Debug, Default)]
+pub struct HdlcDecoder {
+    config: HdlcConfig,
+    buf: Vec<u8>,
+    escaped: bool,
+    overflow: bool,
+    packets: VecDeque<Result<PppPkt>>,
+}
+
+impl HdlcDecoder {
+    pub fn new(config: HdlcConfig) -> Self {
+        Self {
+            config,
+            buf: Vec::new(),
+            escaped: false,
+            overflow: false,
+            packets: VecDeque::new(),
+        }
+    }
+
+    /// Sets the ACCM we negotiated for receiving.
+    pub fn set_accm(&mut self, accm: u32) {
+        self.config.accm = accm;
+    }
+
+    pub fn set_fcs(&mut self, fcs: Fcs) {
+        self.config.fcs = fcs;
+    }
+
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.config.framing = framing;
+    }
+
+    pub fn handle_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            match *byte {
+                HDLC_FLAG => {
+                    // An escaped flag aborts the frame.
+                    if !self.escaped {
+                        self.finish_frame();
+                    }
+
+                    self.buf.clear();
+                    self.escaped = false;
+                    self.overflow = false;
+                }
+                HDLC_ESCAPE => self.escaped = true,
+                // Unescaped control characters in the ACCM
+                // have been inserted by the link and are ignored.
+                byte if byte < 0x20 && self.config.accm & (1 << byte) != 0 && !self.escaped => {}
+                byte => {
+                    let byte = if self.escaped {
+                        self.escaped = false;
+                        byte ^ HDLC_ESCAPE_XOR
+                    } else {
+                        byte
+                    };
+
+                    if self.buf.len() < self.config.max_frame_len {
+                        self.buf.push(byte);
+                    } else {
+                        self.overflow = true;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn poll_packet(&mut self) -> Option<Result<PppPkt>> {
+        self.packets.pop_front()
+    }
+
+    fn finish_frame(&mut self) {
+        if self.overflow {
+            self.packets.push_back(Err(Error::HdlcFrameTooLong));
+            return;
+        }
+
+        if self.buf.is_empty() {
+            return;
+        }
+
+        let fcs_len = self.config.fcs.size();
+        if self.buf.len() <= fcs_len {
+            self.packets
+                .push_back(Err(Error::HdlcFrameTooShort(self.buf.len())));
+            return;
+        }
+
+        if !self.config.fcs.check(&self.buf) {
+            self.packets.push_back(Err(Error::InvalidFcs));
+            return;
+        }
+
+        let payload = &self.buf[..self.buf.len() - fcs_len];
+
+        let mut pkt = PppPkt::default();
+        let result = pkt
+            .deserialize_framed(&mut &payload[..], &self.config.framing)
+            .map(|_| pkt);
+
+        self.packets.push_back(result);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{Deserialize, Error, IpCompressionProtocol, Ipv4Addr, Result, Serialize};
+use crate::{
+    ControlMsg, ControlPacket, Deserialize, Error, IpCompressionProtocol, Ipv4Addr, Result,
+    Serialize, CODE_REJECT_HEADER_LEN,
+};
 
 use std::fmt;
 use std::io::{Read, Write};
@@ -340,6 +343,15 @@ impl IpcpPkt {
         }
     }
 
+    /// Builds a Code-Reject of `rejected`, truncated to fit the peer's `mru`.
+    pub fn new_code_reject_for(identifier: u8, rejected: &IpcpPkt, mru: u16) -> Result<Self> {
+        let mut pkt = Vec::new();
+        rejected.serialize(&mut pkt)?;
+
+        pkt.truncate(usize::from(mru).saturating_sub(CODE_REJECT_HEADER_LEN));
+        Ok(Self::new_code_reject(identifier, pkt))
+    }
+
     pub fn len(&self) -> u16 {
         4 + self.data.len()
     }
@@ -349,6 +361,55 @@ impl IpcpPkt {
     }
 }
 
+impl ControlPacket for IpcpPkt {
+    type Option = IpcpOption;
+
+    fn new_configure_request(identifier: u8, options: Vec<IpcpOption>) -> Self {
+        Self::new_configure_request(identifier, options)
+    }
+
+    fn new_configure_ack(identifier: u8, options: Vec<IpcpOption>) -> Self {
+        Self::new_configure_ack(identifier, options)
+    }
+
+    fn new_configure_nak(identifier: u8, options: Vec<IpcpOption>) -> Self {
+        Self::new_configure_nak(identifier, options)
+    }
+
+    fn new_configure_reject(identifier: u8, options: Vec<IpcpOption>) -> Self {
+        Self::new_configure_reject(identifier, options)
+    }
+
+    fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_request(identifier, data)
+    }
+
+    fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_ack(identifier, data)
+    }
+
+    fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self::new_code_reject(identifier, pkt)
+    }
+
+    fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    fn msg(&self) -> ControlMsg<'_, IpcpOption> {
+        match &self.data {
+            IpcpData::ConfigureRequest(payload) => ControlMsg::ConfigureRequest(&payload.options),
+            IpcpData::ConfigureAck(payload) => ControlMsg::ConfigureAck(&payload.options),
+            IpcpData::ConfigureNak(payload) => ControlMsg::ConfigureNak(&payload.options),
+            IpcpData::ConfigureReject(payload) => ControlMsg::ConfigureReject(&payload.options),
+            IpcpData::TerminateRequest(_) => ControlMsg::TerminateRequest,
+            IpcpData::TerminateAck(_) => ControlMsg::TerminateAck,
+            IpcpData::CodeReject(payload) => ControlMsg::CodeReject(&payload.pkt),
+            IpcpData::Unhandled(..) => ControlMsg::Unknown,
+        }
+    }
+}
+
 impl fmt::Display for IpcpPkt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "IPCP id={}: ", self.identifier)?;
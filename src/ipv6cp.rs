@@ -1,4 +1,6 @@
-use crate::{Deserialize, Error, Result, Serialize};
+use crate::{
+    ControlMsg, ControlPacket, Deserialize, Error, Result, Serialize, CODE_REJECT_HEADER_LEN,
+};
 
 use std::fmt;
 use std::io::{Read, Write};
@@ -305,6 +307,15 @@ impl Ipv6cpPkt {
         }
     }
 
+    /// Builds a Code-Reject of `rejected`, truncated to fit the peer's `mru`.
+    pub fn new_code_reject_for(identifier: u8, rejected: &Ipv6cpPkt, mru: u16) -> Result<Self> {
+        let mut pkt = Vec::new();
+        rejected.serialize(&mut pkt)?;
+
+        pkt.truncate(usize::from(mru).saturating_sub(CODE_REJECT_HEADER_LEN));
+        Ok(Self::new_code_reject(identifier, pkt))
+    }
+
     pub fn len(&self) -> u16 {
         4 + self.data.len()
     }
@@ -314,6 +325,55 @@ impl Ipv6cpPkt {
     }
 }
 
+impl ControlPacket for Ipv6cpPkt {
+    type Option = Ipv6cpOption;
+
+    fn new_configure_request(identifier: u8, options: Vec<Ipv6cpOption>) -> Self {
+        Self::new_configure_request(identifier, options)
+    }
+
+    fn new_configure_ack(identifier: u8, options: Vec<Ipv6cpOption>) -> Self {
+        Self::new_configure_ack(identifier, options)
+    }
+
+    fn new_configure_nak(identifier: u8, options: Vec<Ipv6cpOption>) -> Self {
+        Self::new_configure_nak(identifier, options)
+    }
+
+    fn new_configure_reject(identifier: u8, options: Vec<Ipv6cpOption>) -> Self {
+        Self::new_configure_reject(identifier, options)
+    }
+
+    fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_request(identifier, data)
+    }
+
+    fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_ack(identifier, data)
+    }
+
+    fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self::new_code_reject(identifier, pkt)
+    }
+
+    fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    fn msg(&self) -> ControlMsg<'_, Ipv6cpOption> {
+        match &self.data {
+            Ipv6cpData::ConfigureRequest(payload) => ControlMsg::ConfigureRequest(&payload.options),
+            Ipv6cpData::ConfigureAck(payload) => ControlMsg::ConfigureAck(&payload.options),
+            Ipv6cpData::ConfigureNak(payload) => ControlMsg::ConfigureNak(&payload.options),
+            Ipv6cpData::ConfigureReject(payload) => ControlMsg::ConfigureReject(&payload.options),
+            Ipv6cpData::TerminateRequest(_) => ControlMsg::TerminateRequest,
+            Ipv6cpData::TerminateAck(_) => ControlMsg::TerminateAck,
+            Ipv6cpData::CodeReject(payload) => ControlMsg::CodeReject(&payload.pkt),
+            Ipv6cpData::Unhandled(..) => ControlMsg::Unknown,
+        }
+    }
+}
+
 impl fmt::Display for Ipv6cpPkt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "IPv6CP id={}: ", self.identifier)?;
@@ -1,10 +1,18 @@
-use crate::{AuthProtocol, Deserialize, Error, QualityProtocol, Result, Serialize};
+pub mod keepalive;
+pub mod magic;
+pub mod negotiation;
+
+use crate::{
+    AuthProtocol, ControlMsg, ControlPacket, Deserialize, Error, PppData, PppPkt, QualityProtocol,
+    Result, Serialize, CODE_REJECT_HEADER_LEN,
+};
 
 use std::fmt;
 use std::io::{Read, Write};
 
 use ppproperly_macros::{Deserialize, Serialize};
 
+pub const LCP_VENDOR_EXTENSION: u8 = 0;
 pub const LCP_CONFIGURE_REQUEST: u8 = 1;
 pub const LCP_CONFIGURE_ACK: u8 = 2;
 pub const LCP_CONFIGURE_NAK: u8 = 3;
@@ -16,22 +24,60 @@ pub const LCP_PROTOCOL_REJECT: u8 = 8;
 pub const LCP_ECHO_REQUEST: u8 = 9;
 pub const LCP_ECHO_REPLY: u8 = 10;
 pub const LCP_DISCARD_REQUEST: u8 = 11;
+pub const LCP_IDENTIFICATION: u8 = 12;
+pub const LCP_TIME_REMAINING: u8 = 13;
 
 pub const OPT_MRU: u8 = 1;
+pub const OPT_ASYNC_CONTROL_CHARACTER_MAP: u8 = 2;
 pub const OPT_AUTHENTICATION_PROTOCOL: u8 = 3;
 pub const OPT_QUALITY_PROTOCOL: u8 = 4;
 pub const OPT_MAGIC_NUMBER: u8 = 5;
 pub const OPT_PROTOCOL_FIELD_COMPRESSION: u8 = 7;
 pub const OPT_ADDR_CTL_FIELD_COMPRESSION: u8 = 8;
+pub const OPT_FCS_ALTERNATIVES: u8 = 9;
+pub const OPT_SELF_DESCRIBING_PADDING: u8 = 10;
+pub const OPT_CALLBACK: u8 = 13;
+pub const OPT_MRRU: u8 = 17;
+pub const OPT_SHORT_SEQUENCE_NUMBER_HEADER: u8 = 18;
+pub const OPT_ENDPOINT_DISCRIMINATOR: u8 = 19;
+pub const OPT_INTERNATIONALIZATION: u8 = 28;
+
+pub const FCS_ALTERNATIVE_NULL: u8 = 0x01;
+pub const FCS_ALTERNATIVE_16: u8 = 0x02;
+pub const FCS_ALTERNATIVE_32: u8 = 0x04;
+
+pub const CALLBACK_LOCATION_DETERMINED: u8 = 0;
+pub const CALLBACK_DIALING_STRING: u8 = 1;
+pub const CALLBACK_LOCATION_IDENTIFIER: u8 = 2;
+pub const CALLBACK_E164_NUMBER: u8 = 3;
+pub const CALLBACK_DISTINGUISHED_NAME: u8 = 4;
+
+pub const ENDPOINT_CLASS_NULL: u8 = 0;
+pub const ENDPOINT_CLASS_LOCAL: u8 = 1;
+pub const ENDPOINT_CLASS_IPV4: u8 = 2;
+pub const ENDPOINT_CLASS_MAC: u8 = 3;
+pub const ENDPOINT_CLASS_MAGIC_NUMBER_BLOCK: u8 = 4;
+pub const ENDPOINT_CLASS_PHONE_NUMBER: u8 = 5;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum LcpOpt {
     Mru(u16),
+    AsyncControlCharacterMap(u32),
     AuthenticationProtocol(AuthProtocol),
     QualityProtocol(QualityProtocol),
     MagicNumber(u32),
     ProtocolFieldCompression,
     AddrCtlFieldCompression,
+    /// Bit mask of `FCS_ALTERNATIVE_*` values (RFC 1570).
+    FcsAlternatives(u8),
+    /// Maximum padding value (RFC 1570).
+    SelfDescribingPadding(u8),
+    Callback(Callback),
+    /// Maximum-Received-Reconstructed-Unit, enables multilink (RFC 1990).
+    Mrru(u16),
+    ShortSequenceNumberHeader,
+    EndpointDiscriminator(EndpointDiscriminator),
+    Internationalization(Internationalization),
     Unhandled(u8, Vec<u8>),
 }
 
@@ -39,11 +85,19 @@ impl Serialize for LcpOpt {
     fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
         match self {
             Self::Mru(payload) => payload.serialize(w),
+            Self::AsyncControlCharacterMap(payload) => payload.serialize(w),
             Self::AuthenticationProtocol(payload) => payload.serialize(w),
             Self::QualityProtocol(payload) => payload.serialize(w),
             Self::MagicNumber(payload) => payload.serialize(w),
             Self::ProtocolFieldCompression => Ok(()),
             Self::AddrCtlFieldCompression => Ok(()),
+            Self::FcsAlternatives(payload) => payload.serialize(w),
+            Self::SelfDescribingPadding(payload) => payload.serialize(w),
+            Self::Callback(payload) => payload.serialize(w),
+            Self::Mrru(payload) => payload.serialize(w),
+            Self::ShortSequenceNumberHeader => Ok(()),
+            Self::EndpointDiscriminator(payload) => payload.serialize(w),
+            Self::Internationalization(payload) => payload.serialize(w),
             Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
         }
     }
@@ -53,11 +107,19 @@ impl LcpOpt {
     fn discriminant(&self) -> u8 {
         match self {
             Self::Mru(_) => OPT_MRU,
+            Self::AsyncControlCharacterMap(_) => OPT_ASYNC_CONTROL_CHARACTER_MAP,
             Self::AuthenticationProtocol(_) => OPT_AUTHENTICATION_PROTOCOL,
             Self::QualityProtocol(_) => OPT_QUALITY_PROTOCOL,
             Self::MagicNumber(_) => OPT_MAGIC_NUMBER,
             Self::ProtocolFieldCompression => OPT_PROTOCOL_FIELD_COMPRESSION,
             Self::AddrCtlFieldCompression => OPT_ADDR_CTL_FIELD_COMPRESSION,
+            Self::FcsAlternatives(_) => OPT_FCS_ALTERNATIVES,
+            Self::SelfDescribingPadding(_) => OPT_SELF_DESCRIBING_PADDING,
+            Self::Callback(_) => OPT_CALLBACK,
+            Self::Mrru(_) => OPT_MRRU,
+            Self::ShortSequenceNumberHeader => OPT_SHORT_SEQUENCE_NUMBER_HEADER,
+            Self::EndpointDiscriminator(_) => OPT_ENDPOINT_DISCRIMINATOR,
+            Self::Internationalization(_) => OPT_INTERNATIONALIZATION,
             Self::Unhandled(ty, _) => *ty,
         }
     }
@@ -65,11 +127,19 @@ impl LcpOpt {
     fn len(&self) -> u8 {
         match self {
             Self::Mru(_) => 2,
+            Self::AsyncControlCharacterMap(_) => 4,
             Self::AuthenticationProtocol(payload) => payload.len(),
             Self::QualityProtocol(payload) => payload.len(),
             Self::MagicNumber(_) => 4,
             Self::ProtocolFieldCompression => 0,
             Self::AddrCtlFieldCompression => 0,
+            Self::FcsAlternatives(_) => 1,
+            Self::SelfDescribingPadding(_) => 1,
+            Self::Callback(payload) => payload.len(),
+            Self::Mrru(_) => 2,
+            Self::ShortSequenceNumberHeader => 0,
+            Self::EndpointDiscriminator(payload) => payload.len(),
+            Self::Internationalization(payload) => payload.len(),
             Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
                 panic!(
                     "unhandled lcp option {} length {} exceeds 255",
@@ -92,6 +162,12 @@ impl LcpOpt {
                 tmp.deserialize(r)?;
                 *self = Self::Mru(tmp);
             }
+            OPT_ASYNC_CONTROL_CHARACTER_MAP => {
+                let mut tmp = u32::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::AsyncControlCharacterMap(tmp);
+            }
             OPT_AUTHENTICATION_PROTOCOL => {
                 let mut tmp = AuthProtocol::default();
 
@@ -116,6 +192,45 @@ impl LcpOpt {
             OPT_ADDR_CTL_FIELD_COMPRESSION => {
                 *self = Self::AddrCtlFieldCompression;
             }
+            OPT_FCS_ALTERNATIVES => {
+                let mut tmp = u8::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::FcsAlternatives(tmp);
+            }
+            OPT_SELF_DESCRIBING_PADDING => {
+                let mut tmp = u8::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::SelfDescribingPadding(tmp);
+            }
+            OPT_CALLBACK => {
+                let mut tmp = Callback::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Callback(tmp);
+            }
+            OPT_MRRU => {
+                let mut tmp = u16::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Mrru(tmp);
+            }
+            OPT_SHORT_SEQUENCE_NUMBER_HEADER => {
+                *self = Self::ShortSequenceNumberHeader;
+            }
+            OPT_ENDPOINT_DISCRIMINATOR => {
+                let mut tmp = EndpointDiscriminator::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::EndpointDiscriminator(tmp);
+            }
+            OPT_INTERNATIONALIZATION => {
+                let mut tmp = Internationalization::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Internationalization(tmp);
+            }
             _ => {
                 let mut tmp = Vec::new();
 
@@ -128,6 +243,62 @@ impl LcpOpt {
     }
 }
 
+/// Callback option as described in RFC 1570.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Callback {
+    /// One of the `CALLBACK_*` values, determines the format of `message`.
+    pub operation: u8,
+    pub message: Vec<u8>,
+}
+
+impl Callback {
+    pub fn len(&self) -> u8 {
+        (1 + self.message.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 1
+    }
+}
+
+/// Endpoint Discriminator option as described in RFC 1990.
+/// Identifies the system at the other end of a member link of a bundle.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct EndpointDiscriminator {
+    /// One of the `ENDPOINT_CLASS_*` values, determines the format of `address`.
+    pub class: u8,
+    pub address: Vec<u8>,
+}
+
+impl EndpointDiscriminator {
+    pub fn len(&self) -> u8 {
+        (1 + self.address.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 1
+    }
+}
+
+/// Internationalization option as described in RFC 2484.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Internationalization {
+    /// IANA MIBenum of the charset, e.g. 106 for UTF-8.
+    pub charset: u32,
+    /// RFC 1766 language tag.
+    pub language_tag: String,
+}
+
+impl Internationalization {
+    pub fn len(&self) -> u8 {
+        (4 + self.language_tag.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 4
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct LcpOption {
     #[ppproperly(discriminant_for(field = "value", data_type = "u8"))]
@@ -187,6 +358,9 @@ pub enum LcpData {
     EchoRequest(LcpEchoRequest),
     EchoReply(LcpEchoReply),
     DiscardRequest(LcpDiscardRequest),
+    Identification(LcpIdentification),
+    TimeRemaining(LcpTimeRemaining),
+    VendorExtension(LcpVendorExtension),
     Unhandled(u8, Vec<u8>),
 }
 
@@ -210,6 +384,9 @@ impl Serialize for LcpData {
             Self::EchoRequest(payload) => payload.serialize(w),
             Self::EchoReply(payload) => payload.serialize(w),
             Self::DiscardRequest(payload) => payload.serialize(w),
+            Self::Identification(payload) => payload.serialize(w),
+            Self::TimeRemaining(payload) => payload.serialize(w),
+            Self::VendorExtension(payload) => payload.serialize(w),
             Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
         }
     }
@@ -229,6 +406,9 @@ impl LcpData {
             Self::EchoRequest(_) => LCP_ECHO_REQUEST,
             Self::EchoReply(_) => LCP_ECHO_REPLY,
             Self::DiscardRequest(_) => LCP_DISCARD_REQUEST,
+            Self::Identification(_) => LCP_IDENTIFICATION,
+            Self::TimeRemaining(_) => LCP_TIME_REMAINING,
+            Self::VendorExtension(_) => LCP_VENDOR_EXTENSION,
             Self::Unhandled(ty, _) => *ty,
         }
     }
@@ -246,6 +426,9 @@ impl LcpData {
             Self::EchoRequest(payload) => payload.len(),
             Self::EchoReply(payload) => payload.len(),
             Self::DiscardRequest(payload) => payload.len(),
+            Self::Identification(payload) => payload.len(),
+            Self::TimeRemaining(payload) => payload.len(),
+            Self::VendorExtension(payload) => payload.len(),
             Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
                 panic!(
                     "unhandled lcp code {} length {} exceeds 65535",
@@ -328,6 +511,24 @@ impl LcpData {
                 tmp.deserialize(r)?;
                 *self = Self::DiscardRequest(tmp);
             }
+            LCP_IDENTIFICATION => {
+                let mut tmp = LcpIdentification::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Identification(tmp);
+            }
+            LCP_TIME_REMAINING => {
+                let mut tmp = LcpTimeRemaining::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::TimeRemaining(tmp);
+            }
+            LCP_VENDOR_EXTENSION => {
+                let mut tmp = LcpVendorExtension::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::VendorExtension(tmp);
+            }
             _ => {
                 let mut tmp = Vec::new();
 
@@ -398,6 +599,15 @@ impl LcpPkt {
         }
     }
 
+    /// Builds a Code-Reject of `rejected`, truncated to fit the peer's `mru`.
+    pub fn new_code_reject_for(identifier: u8, rejected: &LcpPkt, mru: u16) -> Result<Self> {
+        let mut pkt = Vec::new();
+        rejected.serialize(&mut pkt)?;
+
+        pkt.truncate(usize::from(mru).saturating_sub(CODE_REJECT_HEADER_LEN));
+        Ok(Self::new_code_reject(identifier, pkt))
+    }
+
     pub fn new_protocol_reject(identifier: u8, protocol: u16, pkt: Vec<u8>) -> Self {
         Self {
             identifier,
@@ -405,6 +615,19 @@ impl LcpPkt {
         }
     }
 
+    /// Builds a Protocol-Reject of `rejected`, truncated to fit the peer's `mru`.
+    pub fn new_protocol_reject_for(identifier: u8, rejected: &PppPkt, mru: u16) -> Result<Self> {
+        let mut pkt = Vec::new();
+        rejected.data.serialize(&mut pkt)?;
+
+        pkt.truncate(usize::from(mru).saturating_sub(CODE_REJECT_HEADER_LEN + 2));
+        Ok(Self::new_protocol_reject(
+            identifier,
+            rejected.protocol(),
+            pkt,
+        ))
+    }
+
     pub fn new_echo_request(identifier: u8, magic: u32, data: Vec<u8>) -> Self {
         Self {
             identifier,
@@ -426,6 +649,42 @@ impl LcpPkt {
         }
     }
 
+    pub fn new_identification(identifier: u8, magic: u32, message: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: LcpData::Identification(LcpIdentification { magic, message }),
+        }
+    }
+
+    pub fn new_time_remaining(identifier: u8, magic: u32, seconds: u32, message: Vec<u8>) -> Self {
+        Self {
+            identifier,
+            data: LcpData::TimeRemaining(LcpTimeRemaining {
+                magic,
+                seconds,
+                message,
+            }),
+        }
+    }
+
+    pub fn new_vendor_extension(
+        identifier: u8,
+        magic: u32,
+        oui: [u8; 3],
+        kind: u8,
+        value: Vec<u8>,
+    ) -> Self {
+        Self {
+            identifier,
+            data: LcpData::VendorExtension(LcpVendorExtension {
+                magic,
+                oui,
+                kind,
+                value,
+            }),
+        }
+    }
+
     pub fn len(&self) -> u16 {
         4 + self.data.len()
     }
@@ -435,6 +694,62 @@ impl LcpPkt {
     }
 }
 
+impl ControlPacket for LcpPkt {
+    type Option = LcpOption;
+
+    fn new_configure_request(identifier: u8, options: Vec<LcpOption>) -> Self {
+        Self::new_configure_request(identifier, options)
+    }
+
+    fn new_configure_ack(identifier: u8, options: Vec<LcpOption>) -> Self {
+        Self::new_configure_ack(identifier, options)
+    }
+
+    fn new_configure_nak(identifier: u8, options: Vec<LcpOption>) -> Self {
+        Self::new_configure_nak(identifier, options)
+    }
+
+    fn new_configure_reject(identifier: u8, options: Vec<LcpOption>) -> Self {
+        Self::new_configure_reject(identifier, options)
+    }
+
+    fn new_terminate_request(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_request(identifier, data)
+    }
+
+    fn new_terminate_ack(identifier: u8, data: Vec<u8>) -> Self {
+        Self::new_terminate_ack(identifier, data)
+    }
+
+    fn new_code_reject(identifier: u8, pkt: Vec<u8>) -> Self {
+        Self::new_code_reject(identifier, pkt)
+    }
+
+    fn identifier(&self) -> u8 {
+        self.identifier
+    }
+
+    fn msg(&self) -> ControlMsg<'_, LcpOption> {
+        match &self.data {
+            LcpData::ConfigureRequest(payload) => ControlMsg::ConfigureRequest(&payload.options),
+            LcpData::ConfigureAck(payload) => ControlMsg::ConfigureAck(&payload.options),
+            LcpData::ConfigureNak(payload) => ControlMsg::ConfigureNak(&payload.options),
+            LcpData::ConfigureReject(payload) => ControlMsg::ConfigureReject(&payload.options),
+            LcpData::TerminateRequest(_) => ControlMsg::TerminateRequest,
+            LcpData::TerminateAck(_) => ControlMsg::TerminateAck,
+            LcpData::CodeReject(payload) => ControlMsg::CodeReject(&payload.pkt),
+            LcpData::ProtocolReject(_) => ControlMsg::Other,
+            LcpData::EchoRequest(_) => ControlMsg::Other,
+            LcpData::EchoReply(_) => ControlMsg::Other,
+            LcpData::DiscardRequest(_) => ControlMsg::Other,
+            LcpData::Identification(_) => ControlMsg::Other,
+            LcpData::TimeRemaining(_) => ControlMsg::Other,
+            LcpData::VendorExtension(_) => ControlMsg::Other,
+            LcpData::Unhandled(..) => ControlMsg::Unknown,
+        }
+    }
+}
+
 impl fmt::Display for LcpPkt {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "LCP id={}: ", self.identifier)?;
@@ -450,6 +765,9 @@ impl fmt::Display for LcpPkt {
             LcpData::EchoRequest(echo_req) => echo_req.fmt(f),
             LcpData::EchoReply(echo_rep) => echo_rep.fmt(f),
             LcpData::DiscardRequest(disc_req) => disc_req.fmt(f),
+            LcpData::Identification(ident) => ident.fmt(f),
+            LcpData::TimeRemaining(time_rem) => time_rem.fmt(f),
+            LcpData::VendorExtension(vendor_ext) => vendor_ext.fmt(f),
             LcpData::Unhandled(ty, payload) => write!(f, "uc={} {:?}", ty, payload),
         }
     }
@@ -637,6 +955,22 @@ pub struct LcpProtocolReject {
 }
 
 impl LcpProtocolReject {
+    /// Parses the rejected packet, e.g. to find out which NCP to shut down.
+    /// Returns [`PppData::Unhandled`] holding the raw Rejected-Information
+    /// if it has been truncated or is malformed.
+    pub fn rejected(&self) -> PppPkt {
+        let mut buf = self.protocol.to_be_bytes().to_vec();
+        buf.extend_from_slice(&self.pkt);
+
+        let mut pkt = PppPkt::default();
+        match pkt.deserialize(&mut buf.as_slice()) {
+            Ok(()) => pkt,
+            Err(_) => PppPkt {
+                data: PppData::Unhandled(self.protocol, self.pkt.clone()),
+            },
+        }
+    }
+
     pub fn len(&self) -> u16 {
         (2 + self.pkt.len()).try_into().unwrap()
     }
@@ -717,3 +1051,108 @@ impl fmt::Display for LcpDiscardRequest {
         write!(f, "Discard-Req {}: {:?}", self.magic, self.data)
     }
 }
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LcpIdentification {
+    pub magic: u32,
+    pub message: Vec<u8>,
+}
+
+impl LcpIdentification {
+    pub fn len(&self) -> u16 {
+        (4 + self.message.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 4
+    }
+}
+
+impl fmt::Display for LcpIdentification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ident {}: {}",
+            self.magic,
+            std::str::from_utf8(&self.message).unwrap_or(&format!("{:?}", self.message))
+        )
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LcpTimeRemaining {
+    pub magic: u32,
+    /// Number of seconds left in the session.
+    pub seconds: u32,
+    pub message: Vec<u8>,
+}
+
+impl LcpTimeRemaining {
+    pub fn len(&self) -> u16 {
+        (8 + self.message.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 8
+    }
+}
+
+impl fmt::Display for LcpTimeRemaining {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Time-Remaining {}: {}s {}",
+            self.magic,
+            self.seconds,
+            std::str::from_utf8(&self.message).unwrap_or(&format!("{:?}", self.message))
+        )
+    }
+}
+
+/// Vendor-Specific packet as described in RFC 2153.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LcpVendorExtension {
+    pub magic: u32,
+    /// IEEE Organizationally Unique Identifier of the vendor.
+    pub oui: [u8; 3],
+    pub kind: u8,
+    pub value: Vec<u8>,
+}
+
+impl Serialize for LcpVendorExtension {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.magic.serialize(w)?;
+        self.oui.serialize(w)?;
+        self.kind.serialize(w)?;
+        self.value.serialize(w)
+    }
+}
+
+impl Deserialize for LcpVendorExtension {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        self.magic.deserialize(r)?;
+        r.read_exact(&mut self.oui)?;
+        self.kind.deserialize(r)?;
+        self.value.deserialize(r)
+    }
+}
+
+impl LcpVendorExtension {
+    pub fn len(&self) -> u16 {
+        (8 + self.value.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 8
+    }
+}
+
+impl fmt::Display for LcpVendorExtension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Vendor-Ext {}: oui={:02x}:{:02x}:{:02x} kind={} {:?}",
+            self.magic, self.oui[0], self.oui[1], self.oui[2], self.kind, self.value
+        )
+    }
+}
@@ -0,0 +1,217 @@
+use super::magic::is_looped_echo;
+use crate::{LcpData, LcpPkt};
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Settings of a [`Keepalive`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Time between two Echo-Requests.
+    pub interval: Duration,
+    /// Number of consecutive unanswered Echo-Requests after which
+    /// the link is considered dead.
+    pub max_missed: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            max_missed: 5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Event {
+    /// Too many Echo-Requests went unanswered.
+    LinkDead,
+    /// An echo carrying our own magic number was received,
+    /// the link is likely looped back.
+    Looped,
+}
+
+/// Link health derived from the Echo-Request / Echo-Reply exchange.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Stats {
+    /// Number of Echo-Requests sent.
+    pub sent: u64,
+    /// Number of matching Echo-Replies received.
+    pub received: u64,
+    /// Number of Echo-Requests that weren't answered within the interval.
+    pub lost: u64,
+    /// Round-trip time of the most recent exchange.
+    pub rtt: Option<Duration>,
+    /// Smoothed round-trip time variation as described in RFC 3550.
+    pub jitter: Duration,
+}
+
+impl Stats {
+    /// Returns the fraction of Echo-Requests that went unanswered.
+    pub fn loss(&self) -> f64 {
+        if self.sent == 0 {
+            0.0
+        } else {
+            self.lost as f64 / self.sent as f64
+        }
+    }
+}
+
+/// Sans-IO LCP echo keepalive.
+///
+/// Sends Echo-Requests while the link is open and answers the peer's
+/// Echo-Requests. Feed received LCP packets into [`Keepalive::handle_packet`]
+/// and call [`Keepalive::handle_timeout`] once [`Keepalive::poll_timeout`]
+/// has passed. Packets to send are returned by [`Keepalive::poll_transmit`].
+#[derive(Clone, Debug)]
+pub struct Keepalive {
+    config: Config,
+    magic_number: u32,
+    next_identifier: u8,
+    outstanding: Option<(u8, Instant)>,
+    missed: u32,
+    deadline: Option<Instant>,
+    stats: Stats,
+    transmit: VecDeque<LcpPkt>,
+    events: VecDeque<Event>,
+}
+
+impl Keepalive {
+    /// Creates a keepalive using our negotiated magic number,
+    /// 0 if none was negotiated.
+    pub fn new(config: Config, magic_number: u32) -> Self {
+        Self {
+            config,
+            magic_number,
+            next_identifier: 0,
+            outstanding: None,
+            missed: 0,
+            deadline: None,
+            stats: Stats::default(),
+            transmit: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Starts sending Echo-Requests, e.g. on This-Layer-Up.
+    pub fn start(&mut self, now: Instant) {
+        self.outstanding = None;
+        self.missed = 0;
+
+        self.send_echo_request(now);
+    }
+
+    /// Stops sending Echo-Requests, e.g. on This-Layer-Down.
+    pub fn stop(&mut self) {
+        self.outstanding = None;
+        self.deadline = None;
+    }
+
+    pub fn handle_packet(&mut self, pkt: &LcpPkt, now: Instant) {
+        match &pkt.data {
+            LcpData::EchoRequest(echo_request) => {
+                if is_looped_echo(self.magic_number, echo_request.magic) {
+                    self.events.push_back(Event::Looped);
+                    return;
+                }
+
+                self.transmit.push_back(LcpPkt::new_echo_reply(
+                    pkt.identifier,
+                    self.magic_number,
+                    echo_request.data.clone(),
+                ));
+            }
+            LcpData::EchoReply(echo_reply) => {
+                // Our own magic number means the request has been looped back.
+                if is_looped_echo(self.magic_number, echo_reply.magic) {
+                    self.events.push_back(Event::Looped);
+                    return;
+                }
+
+                let sent = match self.outstanding {
+                    Some((identifier, sent)) if identifier == pkt.identifier => sent,
+                    _ => return,
+                };
+
+                self.outstanding = None;
+                self.missed = 0;
+                self.update_rtt(now.saturating_duration_since(sent));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_timeout(&mut self, now: Instant) {
+        match self.deadline {
+            Some(deadline) if deadline <= now => {}
+            _ => return,
+        }
+
+        if self.outstanding.take().is_some() {
+            self.missed += 1;
+            self.stats.lost += 1;
+
+            if self.missed >= self.config.max_missed {
+                self.deadline = None;
+                self.events.push_back(Event::LinkDead);
+
+                return;
+            }
+        }
+
+        self.send_echo_request(now);
+    }
+
+    /// Returns the point in time at which [`Keepalive::handle_timeout`] needs to be called.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<LcpPkt> {
+        self.transmit.pop_front()
+    }
+
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Returns the number of consecutive unanswered Echo-Requests.
+    pub fn missed(&self) -> u32 {
+        self.missed
+    }
+
+    fn send_echo_request(&mut self, now: Instant) {
+        let identifier = self.next_identifier;
+        self.next_identifier = self.next_identifier.wrapping_add(1);
+
+        self.transmit.push_back(LcpPkt::new_echo_request(
+            identifier,
+            self.magic_number,
+            Vec::new(),
+        ));
+
+        self.outstanding = Some((identifier, now));
+        self.deadline = Some(now + self.config.interval);
+        self.stats.sent += 1;
+    }
+
+    fn update_rtt(&mut self, rtt: Duration) {
+        if let Some(prev) = self.stats.rtt {
+            // J += (|D| - J) / 16
+            let d = rtt.abs_diff(prev);
+            self.stats.jitter = if d > self.stats.jitter {
+                self.stats.jitter + (d - self.stats.jitter) / 16
+            } else {
+                self.stats.jitter - (self.stats.jitter - d) / 16
+            };
+        }
+
+        self.stats.rtt = Some(rtt);
+        self.stats.received += 1;
+    }
+}
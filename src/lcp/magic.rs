@@ -0,0 +1,130 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// Source of random magic numbers.
+pub trait MagicRng {
+    fn next_u32(&mut self) -> u32;
+}
+
+/// xorshift64* generator seeded from the standard library's
+/// per-process random hasher keys. Not suitable for cryptography.
+#[derive(Clone, Debug)]
+pub struct StdRng(u64);
+
+impl Default for StdRng {
+    fn default() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        Self(seed | 1)
+    }
+}
+
+impl MagicRng for StdRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as u32
+    }
+}
+
+/// RFC 1661 magic number selection and loopback detection.
+///
+/// Holds our current magic number and counts how often it is seen
+/// coming back from the peer. Once that has happened
+/// `max_repeats` times the link is considered looped back.
+#[derive(Clone, Debug)]
+pub struct Magic<R: MagicRng = StdRng> {
+    rng: R,
+    magic_number: u32,
+    max_repeats: u32,
+    repeats: u32,
+}
+
+impl Magic {
+    pub fn new(max_repeats: u32) -> Self {
+        Self::with_rng(StdRng::default(), max_repeats)
+    }
+}
+
+impl<R: MagicRng> Magic<R> {
+    pub fn with_rng(mut rng: R, max_repeats: u32) -> Self {
+        let magic_number = random(&mut rng, 0);
+
+        Self {
+            rng,
+            magic_number,
+            max_repeats,
+            repeats: 0,
+        }
+    }
+
+    /// Returns our current magic number, never 0.
+    pub fn magic_number(&self) -> u32 {
+        self.magic_number
+    }
+
+    /// Returns whether our magic number has come back too often.
+    pub fn is_looped(&self) -> bool {
+        self.repeats >= self.max_repeats
+    }
+
+    /// Forgets previous collisions, e.g. once the link has been opened.
+    pub fn reset(&mut self) {
+        self.repeats = 0;
+    }
+
+    /// Checks the Magic-Number of a Configure-Request of the peer.
+    /// Returns the value to Nak it with if it isn't acceptable.
+    ///
+    /// Our own number indicates a possible loopback. In that case
+    /// our number is replaced too, so the next Configure-Request
+    /// differs from the one that came back.
+    pub fn check_request(&mut self, magic_number: u32) -> Option<u32> {
+        if magic_number == self.magic_number {
+            self.repeats += 1;
+            self.magic_number = random(&mut self.rng, magic_number);
+        } else if magic_number != 0 {
+            return None;
+        }
+
+        Some(random(&mut self.rng, self.magic_number))
+    }
+
+    /// Handles a Configure-Nak of our Magic-Number suggesting `magic_number`.
+    /// A new number is picked that differs from the suggestion.
+    pub fn handle_nak(&mut self, magic_number: u32) {
+        if magic_number == self.magic_number {
+            self.repeats += 1;
+        }
+
+        self.magic_number = random(&mut self.rng, magic_number);
+    }
+
+    /// Checks the Magic-Number of a received Echo-Request or Echo-Reply.
+    /// Returns true if it is our own, i.e. the echo has been looped back.
+    pub fn check_echo(&mut self, magic_number: u32) -> bool {
+        let looped = is_looped_echo(self.magic_number, magic_number);
+        if looped {
+            self.repeats += 1;
+        }
+
+        looped
+    }
+}
+
+/// Returns whether an Echo-Request or Echo-Reply carrying `magic_number`
+/// has been looped back, i.e. carries `own`, our non-zero magic number.
+/// A zero `own` means no magic number has been negotiated.
+pub fn is_looped_echo(own: u32, magic_number: u32) -> bool {
+    own != 0 && magic_number == own
+}
+
+fn random<R: MagicRng>(rng: &mut R, avoid: u32) -> u32 {
+    loop {
+        let magic_number = rng.next_u32();
+        if magic_number != 0 && magic_number != avoid {
+            return magic_number;
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use super::magic::{Magic, MagicRng, StdRng};
+use crate::{
+    AuthProto, ControlProtocol, EndpointDiscriminator, Framing, FsmVerdict, LcpConfigureRequest,
+    LcpOpt, LcpOption, LcpPkt, DEFAULT_MRU,
+};
+
+use std::ops::RangeInclusive;
+
+/// Local LCP settings a [`Negotiator`] enforces.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Policy {
+    /// MRU to request. `None` or 1500 leaves the option out.
+    pub mru: Option<u16>,
+    /// MRUs the peer may request.
+    pub peer_mru: RangeInclusive<u16>,
+    /// Smallest MRU or MRRU a Configure-Nak of the peer may lower our request to.
+    /// Naks never raise them above [`Policy::mru`] (or 1500) and [`Policy::mrru`].
+    pub min_mru: u16,
+    /// Authentication protocol the peer is required to use.
+    /// The peer may still reject it, so check [`Negotiator::local`]
+    /// before considering the link authenticated.
+    pub auth: Option<AuthProto>,
+    /// Authentication protocols we can use ourselves, most preferred first.
+    /// Empty rejects any authentication request of the peer.
+    pub peer_auth: Vec<AuthProto>,
+    /// Whether to request and accept Protocol-Field-Compression.
+    pub pfc: bool,
+    /// Whether to request and accept Address-and-Control-Field-Compression.
+    pub acfc: bool,
+    /// MRRU to request and accept, enabling multilink. `None` rejects multilink.
+    pub mrru: Option<u16>,
+    /// Whether to request and accept the multilink Short Sequence Number Header Format.
+    pub ssnhf: bool,
+    /// Endpoint Discriminator to send if multilink is enabled.
+    pub endpoint_discriminator: Option<EndpointDiscriminator>,
+    /// Whether to request a random Magic-Number.
+    pub magic_number: bool,
+    /// Number of times our Magic-Number may come back
+    /// before the link is considered looped back.
+    pub max_loopback: u32,
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            mru: None,
+            peer_mru: 64..=DEFAULT_MRU,
+            min_mru: 1280,
+            auth: None,
+            peer_auth: Vec::new(),
+            pfc: false,
+            acfc: false,
+            mrru: None,
+            ssnhf: false,
+            endpoint_discriminator: None,
+            magic_number: true,
+            max_loopback: 5,
+        }
+    }
+}
+
+/// Option values in effect for one direction of the link.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Negotiated {
+    pub mru: Option<u16>,
+    pub auth: Option<AuthProto>,
+    pub magic_number: Option<u32>,
+    pub pfc: bool,
+    pub acfc: bool,
+    pub mrru: Option<u16>,
+    pub ssnhf: bool,
+    pub endpoint_discriminator: Option<EndpointDiscriminator>,
+}
+
+impl Negotiated {
+    fn from_options(options: &[LcpOption]) -> Self {
+        let mut negotiated = Self::default();
+
+        for option in options {
+            match &option.value {
+                LcpOpt::Mru(mru) => negotiated.mru = Some(*mru),
+                LcpOpt::AuthenticationProtocol(auth) => {
+                    negotiated.auth = Some(auth.protocol.clone())
+                }
+                LcpOpt::MagicNumber(magic_number) => negotiated.magic_number = Some(*magic_number),
+                LcpOpt::ProtocolFieldCompression => negotiated.pfc = true,
+                LcpOpt::AddrCtlFieldCompression => negotiated.acfc = true,
+                LcpOpt::Mrru(mrru) => negotiated.mrru = Some(*mrru),
+                LcpOpt::ShortSequenceNumberHeader => negotiated.ssnhf = true,
+                LcpOpt::EndpointDiscriminator(endpoint_discriminator) => {
+                    negotiated.endpoint_discriminator = Some(endpoint_discriminator.clone())
+                }
+                _ => {}
+            }
+        }
+
+        negotiated
+    }
+}
+
+/// LCP option negotiation following a [`Policy`].
+///
+/// Answers Configure-Requests of the peer and adjusts our own requests
+/// to its Configure-Naks and Configure-Rejects. Plug it into an
+/// [`Fsm`](crate::Fsm) to run LCP.
+#[derive(Clone, Debug)]
+pub struct Negotiator<R: MagicRng = StdRng> {
+    policy: Policy,
+    magic: Magic<R>,
+    request: Negotiated,
+    local: Negotiated,
+    peer: Negotiated,
+}
+
+impl Negotiator {
+    pub fn new(policy: Policy) -> Self {
+        Self::with_rng(policy, StdRng::default())
+    }
+}
+
+impl<R: MagicRng> Negotiator<R> {
+    /// Creates a negotiator drawing its magic numbers from `rng`.
+    pub fn with_rng(policy: Policy, rng: R) -> Self {
+        let magic = Magic::with_rng(rng, policy.max_loopback);
+        let request = Negotiated {
+            mru: policy.mru.filter(|mru| *mru != DEFAULT_MRU),
+            auth: policy.auth.clone(),
+            magic_number: policy.magic_number.then(|| magic.magic_number()),
+            pfc: policy.pfc,
+            acfc: policy.acfc,
+            mrru: policy.mrru,
+            ssnhf: policy.mrru.is_some() && policy.ssnhf,
+            endpoint_discriminator: policy
+                .endpoint_discriminator
+                .clone()
+                .filter(|_| policy.mrru.is_some()),
+        };
+
+        Self {
+            policy,
+            magic,
+            request,
+            local: Negotiated::default(),
+            peer: Negotiated::default(),
+        }
+    }
+
+    pub fn policy(&self) -> &Policy {
+        &self.policy
+    }
+
+    /// Returns the options the peer has acknowledged,
+    /// i.e. how the peer sends to us.
+    pub fn local(&self) -> &Negotiated {
+        &self.local
+    }
+
+    /// Returns the options we have acknowledged,
+    /// i.e. how we send to the peer.
+    pub fn peer(&self) -> &Negotiated {
+        &self.peer
+    }
+
+    /// Returns the framing of packets we send.
+    /// The peer has asked for the compression it can receive.
+    pub fn transmit_framing(&self, addr_ctl: bool) -> Framing {
+        Framing {
+            addr_ctl,
+            pfc: self.peer.pfc,
+            acfc: self.peer.acfc,
+            ssnhf: self.peer.ssnhf,
+        }
+    }
+
+    /// Returns the framing of packets we receive.
+    /// Depends on the compression the peer accepted from our request.
+    pub fn receive_framing(&self, addr_ctl: bool) -> Framing {
+        Framing {
+            addr_ctl,
+            pfc: self.local.pfc,
+            acfc: self.local.acfc,
+            ssnhf: self.local.ssnhf,
+        }
+    }
+
+    /// Returns our current magic number.
+    pub fn magic_number(&self) -> u32 {
+        self.magic.magic_number()
+    }
+
+    /// Returns whether the link appears to be looped back.
+    /// The caller should close the link in that case.
+    pub fn is_looped(&self) -> bool {
+        self.magic.is_looped()
+    }
+
+    /// Returns the reply to a Configure-Request of the peer.
+    pub fn reply(&mut self, identifier: u8, request: &LcpConfigureRequest) -> LcpPkt {
+        match self.check_request(&request.options) {
+            FsmVerdict::Ack => LcpPkt::new_configure_ack(identifier, request.options.clone()),
+            FsmVerdict::Nak(options) => LcpPkt::new_configure_nak(identifier, options),
+            FsmVerdict::Reject(options) => LcpPkt::new_configure_reject(identifier, options),
+        }
+    }
+
+    /// Limits a size suggested by the peer to what we are able to receive.
+    fn clamp_nak(&self, value: u16, limit: u16) -> u16 {
+        value.min(limit).max(self.policy.min_mru.min(limit))
+    }
+
+    fn check_option(&mut self, option: &LcpOpt) -> Verdict {
+        match option {
+            LcpOpt::Mru(mru) => {
+                if self.policy.peer_mru.contains(mru) {
+                    Verdict::Ack
+                } else {
+                    let hint =
+                        (*mru).clamp(*self.policy.peer_mru.start(), *self.policy.peer_mru.end());
+                    Verdict::Nak(LcpOpt::Mru(hint))
+                }
+            }
+            LcpOpt::AuthenticationProtocol(auth) => {
+                if self.policy.peer_auth.contains(&auth.protocol) {
+                    Verdict::Ack
+                } else {
+                    match self.policy.peer_auth.first() {
+                        Some(hint) => {
+                            Verdict::Nak(LcpOpt::AuthenticationProtocol(hint.clone().into()))
+                        }
+                        None => Verdict::Reject,
+                    }
+                }
+            }
+            LcpOpt::MagicNumber(magic_number) => match self.magic.check_request(*magic_number) {
+                Some(hint) => Verdict::Nak(LcpOpt::MagicNumber(hint)),
+                None => Verdict::Ack,
+            },
+            LcpOpt::ProtocolFieldCompression if self.policy.pfc => Verdict::Ack,
+            LcpOpt::AddrCtlFieldCompression if self.policy.acfc => Verdict::Ack,
+            LcpOpt::Mrru(_) if self.policy.mrru.is_some() => Verdict::Ack,
+            LcpOpt::ShortSequenceNumberHeader
+                if self.policy.mrru.is_some() && self.policy.ssnhf =>
+            {
+                Verdict::Ack
+            }
+            LcpOpt::EndpointDiscriminator(_) if self.policy.mrru.is_some() => Verdict::Ack,
+            _ => Verdict::Reject,
+        }
+    }
+}
+
+enum Verdict {
+    Ack,
+    Nak(LcpOpt),
+    Reject,
+}
+
+impl<R: MagicRng> ControlProtocol for Negotiator<R> {
+    type Packet = LcpPkt;
+
+    fn request_options(&mut self) -> Vec<LcpOption> {
+        let mut options = Vec::new();
+
+        if let Some(mru) = self.request.mru {
+            options.push(LcpOpt::Mru(mru).into());
+        }
+        if let Some(auth) = &self.request.auth {
+            options.push(LcpOpt::AuthenticationProtocol(auth.clone().into()).into());
+        }
+        if self.request.magic_number.is_some() {
+            options.push(LcpOpt::MagicNumber(self.magic.magic_number()).into());
+        }
+        if self.request.pfc {
+            options.push(LcpOpt::ProtocolFieldCompression.into());
+        }
+        if self.request.acfc {
+            options.push(LcpOpt::AddrCtlFieldCompression.into());
+        }
+        if let Some(mrru) = self.request.mrru {
+            options.push(LcpOpt::Mrru(mrru).into());
+        }
+        if self.request.ssnhf {
+            options.push(LcpOpt::ShortSequenceNumberHeader.into());
+        }
+        if let Some(endpoint_discriminator) = &self.request.endpoint_discriminator {
+            options.push(LcpOpt::EndpointDiscriminator(endpoint_discriminator.clone()).into());
+        }
+
+        options
+    }
+
+    fn check_request(&mut self, options: &[LcpOption]) -> FsmVerdict<LcpOption> {
+        let mut nak = Vec::new();
+        let mut reject = Vec::new();
+
+        for option in options {
+            match self.check_option(&option.value) {
+                Verdict::Ack => {}
+                Verdict::Nak(hint) => nak.push(hint.into()),
+                Verdict::Reject => reject.push(option.clone()),
+            }
+        }
+
+        // Rejects take precedence, the peer has to drop those first.
+        if !reject.is_empty() {
+            FsmVerdict::Reject(reject)
+        } else if !nak.is_empty() {
+            FsmVerdict::Nak(nak)
+        } else {
+            self.peer = Negotiated::from_options(options);
+            FsmVerdict::Ack
+        }
+    }
+
+    fn handle_ack(&mut self, options: &[LcpOption]) {
+        // A looped-back link never acknowledges our own request.
+        self.magic.reset();
+        self.local = Negotiated::from_options(options);
+    }
+
+    fn handle_nak(&mut self, options: &[LcpOption]) {
+        for option in options {
+            match &option.value {
+                LcpOpt::Mru(mru) => {
+                    let limit = self.policy.mru.unwrap_or(DEFAULT_MRU);
+                    self.request.mru = Some(self.clamp_nak(*mru, limit));
+                }
+                LcpOpt::Mrru(mrru) => {
+                    if let Some(limit) = self.policy.mrru {
+                        self.request.mrru = Some(self.clamp_nak(*mrru, limit));
+                    }
+                }
+                LcpOpt::MagicNumber(magic_number) => self.magic.handle_nak(*magic_number),
+                // An authentication protocol other than the one we require
+                // is not acceptable. Keep asking, the peer will have to
+                // comply or reject the option.
+                LcpOpt::AuthenticationProtocol(_) => {}
+                _ => {}
+            }
+        }
+    }
+
+    fn handle_reject(&mut self, options: &[LcpOption]) {
+        for option in options {
+            match &option.value {
+                LcpOpt::Mru(_) => self.request.mru = None,
+                LcpOpt::AuthenticationProtocol(_) => self.request.auth = None,
+                LcpOpt::MagicNumber(_) => self.request.magic_number = None,
+                LcpOpt::ProtocolFieldCompression => self.request.pfc = false,
+                LcpOpt::AddrCtlFieldCompression => self.request.acfc = false,
+                // Without MRRU the peer doesn't do multilink at all.
+                LcpOpt::Mrru(_) => {
+                    self.request.mrru = None;
+                    self.request.ssnhf = false;
+                    self.request.endpoint_discriminator = None;
+                }
+                LcpOpt::ShortSequenceNumberHeader => self.request.ssnhf = false,
+                LcpOpt::EndpointDiscriminator(_) => self.request.endpoint_discriminator = None,
+                _ => {}
+            }
+        }
+    }
+}
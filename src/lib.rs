@@ -1,12 +1,24 @@
+pub mod ccp;
+pub use ccp::*;
+
 pub mod chap;
 pub use chap::*;
 
 pub mod de;
 pub use de::*;
 
+pub mod ecp;
+pub use ecp::*;
+
 pub mod error;
 pub use error::*;
 
+pub mod fsm;
+pub use fsm::*;
+
+pub mod hdlc;
+pub use hdlc::*;
+
 pub mod ipcp;
 pub use ipcp::*;
 
@@ -16,6 +28,12 @@ pub use ipv6cp::*;
 pub mod lcp;
 pub use lcp::*;
 
+pub mod lqr;
+pub use lqr::*;
+
+pub mod multilink;
+pub use multilink::*;
+
 pub mod pap;
 pub use pap::*;
 
@@ -120,7 +138,15 @@ mod tests {
         let tags: Vec<PppoeTag> = vec![
             PppoeVal::HostUniq(vec![13, 37]).into(),
             PppoeVal::GenericError(String::from("err")).into(),
-            PppoeVal::Metrics.into(),
+            PppoeVal::Metrics(Metrics {
+                receive_only: true,
+                rlq: 100,
+                resources: 50,
+                latency: 20,
+                cur_data_rate: 1000,
+                max_data_rate: 10000,
+            })
+            .into(),
         ];
 
         let mut buf = Vec::new();
@@ -130,7 +156,7 @@ mod tests {
             &buf,
             &[
                 0x01, 0x03, 0x00, 0x02, 0x0d, 0x25, 0x02, 0x03, 0x00, 0x03, 0x65, 0x72, 0x72, 0x01,
-                0x07, 0x00, 0x00,
+                0x07, 0x00, 0x0a, 0x00, 0x01, 0x64, 0x32, 0x00, 0x14, 0x03, 0xe8, 0x27, 0x10,
             ]
         );
         Ok(())
@@ -142,7 +168,7 @@ mod tests {
 
         let buf = [
             0x01, 0x03, 0x00, 0x02, 0x0d, 0x25, 0x02, 0x03, 0x00, 0x03, 0x65, 0x72, 0x72, 0x01,
-            0x07, 0x00, 0x00,
+            0x07, 0x00, 0x0a, 0x00, 0x01, 0x64, 0x32, 0x00, 0x14, 0x03, 0xe8, 0x27, 0x10,
         ];
         tags.deserialize(&mut buf.as_ref())?;
 
@@ -151,7 +177,15 @@ mod tests {
             vec![
                 PppoeVal::HostUniq(vec![13, 37]).into(),
                 PppoeVal::GenericError(String::from("err")).into(),
-                PppoeVal::Metrics.into(),
+                PppoeVal::Metrics(Metrics {
+                    receive_only: true,
+                    rlq: 100,
+                    resources: 50,
+                    latency: 20,
+                    cur_data_rate: 1000,
+                    max_data_rate: 10000,
+                })
+                .into(),
             ]
         );
         Ok(())
@@ -1954,4 +1988,3120 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_discovery_client_establishes_session() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let mut config = discovery::Config::new(host_mac);
+        config.host_uniq = vec![13, 37];
+
+        let now = std::time::Instant::now();
+        let mut client = discovery::Client::new(config);
+        client.start(now);
+
+        assert_eq!(
+            client.poll_transmit(),
+            Some(PppoePkt::new_padi(
+                host_mac,
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::HostUniq(vec![13, 37]).into()
+                ]
+            ))
+        );
+
+        client.handle_packet(
+            &PppoePkt::new_pado(
+                host_mac,
+                ac_mac,
+                vec![
+                    PppoeVal::AcName("isp_ac".into()).into(),
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::HostUniq(vec![13, 37]).into(),
+                    PppoeVal::AcCookie(vec![0x41, 0x41]).into(),
+                ],
+            ),
+            now,
+        );
+
+        assert_eq!(
+            client.poll_transmit(),
+            Some(PppoePkt::new_padr(
+                ac_mac,
+                host_mac,
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::HostUniq(vec![13, 37]).into(),
+                    PppoeVal::AcCookie(vec![0x41, 0x41]).into()
+                ]
+            ))
+        );
+
+        client.handle_packet(
+            &PppoePkt::new_pads(
+                host_mac,
+                ac_mac,
+                1,
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::HostUniq(vec![13, 37]).into(),
+                ],
+            ),
+            now,
+        );
+
+        assert_eq!(
+            client.poll_event(),
+            Some(discovery::Event::SessionEstablished {
+                session_id: 1,
+                ac_mac
+            })
+        );
+        assert!(client.is_established());
+        Ok(())
+    }
+
+    #[test]
+    fn test_discovery_client_retransmits_with_backoff() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+
+        let mut config = discovery::Config::new(host_mac);
+        config.max_attempts = 3;
+
+        let start = std::time::Instant::now();
+        let mut client = discovery::Client::new(config);
+        client.start(start);
+
+        assert!(client.poll_transmit().is_some());
+        assert_eq!(
+            client.poll_timeout(),
+            Some(start + std::time::Duration::from_secs(1))
+        );
+
+        client.handle_timeout(start + std::time::Duration::from_secs(1));
+        assert!(client.poll_transmit().is_some());
+        assert_eq!(
+            client.poll_timeout(),
+            Some(start + std::time::Duration::from_secs(3))
+        );
+
+        client.handle_timeout(start + std::time::Duration::from_secs(3));
+        assert!(client.poll_transmit().is_some());
+        assert_eq!(
+            client.poll_timeout(),
+            Some(start + std::time::Duration::from_secs(7))
+        );
+
+        client.handle_timeout(start + std::time::Duration::from_secs(7));
+        assert!(client.poll_transmit().is_none());
+        assert_eq!(
+            client.poll_event(),
+            Some(discovery::Event::Failed(discovery::Failure::NoOffer))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_discovery_client_service_name_error() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let mut config = discovery::Config::new(host_mac);
+        config.service_name = "isp_svc".into();
+
+        let now = std::time::Instant::now();
+        let mut client = discovery::Client::new(config);
+        client.start(now);
+
+        client.handle_packet(
+            &PppoePkt::new_pado(
+                host_mac,
+                ac_mac,
+                vec![
+                    PppoeVal::AcName("isp_ac".into()).into(),
+                    PppoeVal::ServiceName("other_svc".into()).into(),
+                ],
+            ),
+            now,
+        );
+        assert!(client.poll_timeout().is_some());
+
+        client.handle_packet(
+            &PppoePkt::new_pado(
+                host_mac,
+                ac_mac,
+                vec![
+                    PppoeVal::AcName("isp_ac".into()).into(),
+                    PppoeVal::ServiceName("isp_svc".into()).into(),
+                ],
+            ),
+            now,
+        );
+        client.handle_packet(
+            &PppoePkt::new_pads(
+                host_mac,
+                ac_mac,
+                0,
+                vec![PppoeVal::ServiceNameError("unavailable".into()).into()],
+            ),
+            now,
+        );
+
+        assert_eq!(
+            client.poll_event(),
+            Some(discovery::Event::Failed(
+                discovery::Failure::ServiceNameError("unavailable".into())
+            ))
+        );
+        assert!(client.is_failed());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_serves_discovery_client() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let mut config = discovery::Config::new(host_mac);
+        config.service_name = "isp_svc".into();
+        config.host_uniq = vec![13, 37];
+
+        let mut ac_config = ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec());
+        ac_config.service_names = vec!["isp_svc".into()];
+
+        let now = std::time::Instant::now();
+        let mut client = discovery::Client::new(config);
+        let mut server = ac::Server::new(ac_config);
+        client.start(now);
+
+        while let Some(pkt) = client.poll_transmit() {
+            server.handle_packet(&pkt, now);
+            while let Some(pkt) = server.poll_transmit() {
+                client.handle_packet(&pkt, now);
+            }
+        }
+
+        assert_eq!(
+            client.poll_event(),
+            Some(discovery::Event::SessionEstablished {
+                session_id: 1,
+                ac_mac
+            })
+        );
+        assert_eq!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted {
+                session_id: 1,
+                client_mac: host_mac,
+                service_name: "isp_svc".into(),
+                agent_circuit_id: None,
+                agent_remote_id: None,
+            })
+        );
+        assert_eq!(server.sessions().collect::<Vec<_>>(), vec![(host_mac, 1)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_rejects_invalid_cookie() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let now = std::time::Instant::now();
+        let mut server =
+            ac::Server::new(ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec()));
+
+        server.handle_packet(
+            &PppoePkt::new_padr(
+                ac_mac,
+                host_mac,
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::AcCookie(vec![0x41, 0x41]).into(),
+                ],
+            ),
+            now,
+        );
+        assert_eq!(server.poll_transmit(), None);
+
+        server.handle_packet(
+            &PppoePkt::new_padr(
+                ac_mac,
+                host_mac,
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::AcCookie(server.cookie(host_mac)).into(),
+                ],
+            ),
+            now,
+        );
+        assert_eq!(
+            server.poll_transmit(),
+            Some(PppoePkt::new_pads(
+                host_mac,
+                ac_mac,
+                1,
+                vec![PppoeVal::ServiceName(String::new()).into()]
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_removes_session_on_padt() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let now = std::time::Instant::now();
+        let mut server =
+            ac::Server::new(ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec()));
+
+        for host_uniq in [1, 2] {
+            server.handle_packet(
+                &PppoePkt::new_padr(
+                    ac_mac,
+                    host_mac,
+                    vec![
+                        PppoeVal::ServiceName(String::new()).into(),
+                        PppoeVal::HostUniq(vec![host_uniq]).into(),
+                        PppoeVal::AcCookie(server.cookie(host_mac)).into(),
+                    ],
+                ),
+                now,
+            );
+        }
+        assert_eq!(
+            server.sessions().collect::<Vec<_>>(),
+            vec![(host_mac, 1), (host_mac, 2)]
+        );
+
+        server.handle_packet(&PppoePkt::new_padt(ac_mac, host_mac, 1, Vec::new()), now);
+        assert_eq!(server.sessions().collect::<Vec<_>>(), vec![(host_mac, 2)]);
+        assert_eq!(server.session(1), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_resends_pads_for_retransmitted_padr() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let now = std::time::Instant::now();
+        let mut server =
+            ac::Server::new(ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec()));
+
+        let padr = PppoePkt::new_padr(
+            ac_mac,
+            host_mac,
+            vec![
+                PppoeVal::ServiceName(String::new()).into(),
+                PppoeVal::HostUniq(vec![0x13, 0x37]).into(),
+                PppoeVal::AcCookie(server.cookie(host_mac)).into(),
+            ],
+        );
+        let pads = PppoePkt::new_pads(
+            host_mac,
+            ac_mac,
+            1,
+            vec![
+                PppoeVal::ServiceName(String::new()).into(),
+                PppoeVal::HostUniq(vec![0x13, 0x37]).into(),
+            ],
+        );
+
+        // The first PADS is lost, the client sends the same PADR again.
+        server.handle_packet(&padr, now);
+        assert_eq!(server.poll_transmit(), Some(pads.clone()));
+        server.handle_packet(&padr, now);
+        assert_eq!(server.poll_transmit(), Some(pads));
+
+        assert_eq!(server.sessions().collect::<Vec<_>>(), vec![(host_mac, 1)]);
+        assert!(matches!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted { session_id: 1, .. })
+        ));
+        assert_eq!(server.poll_event(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_replaces_session_of_restarted_client() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let now = std::time::Instant::now();
+        let mut server =
+            ac::Server::new(ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec()));
+
+        let padr = PppoePkt::new_padr(
+            ac_mac,
+            host_mac,
+            vec![
+                PppoeVal::ServiceName(String::new()).into(),
+                PppoeVal::AcCookie(server.cookie(host_mac)).into(),
+            ],
+        );
+
+        server.handle_packet(&padr, now);
+        assert!(server.poll_transmit().is_some());
+        assert!(matches!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted { session_id: 1, .. })
+        ));
+
+        // The same MAC on another VLAN is a different subscriber.
+        server.handle_packet(&padr.clone().with_vlans(vec![VlanTag::new(7)]), now);
+        assert_eq!(
+            server.poll_transmit(),
+            Some(
+                PppoePkt::new_pads(
+                    host_mac,
+                    ac_mac,
+                    2,
+                    vec![PppoeVal::ServiceName(String::new()).into()]
+                )
+                .with_vlans(vec![VlanTag::new(7)])
+            )
+        );
+        assert!(matches!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted { session_id: 2, .. })
+        ));
+
+        // The client rebooted without sending a PADT and starts over.
+        server.handle_packet(&padr, now + std::time::Duration::from_secs(60));
+        assert_eq!(
+            server.poll_transmit(),
+            Some(PppoePkt::new_padt(host_mac, ac_mac, 1, Vec::new()))
+        );
+        assert_eq!(
+            server.poll_transmit(),
+            Some(PppoePkt::new_pads(
+                host_mac,
+                ac_mac,
+                3,
+                vec![PppoeVal::ServiceName(String::new()).into()]
+            ))
+        );
+        assert_eq!(
+            server.poll_event(),
+            Some(ac::Event::SessionTerminated {
+                session_id: 1,
+                client_mac: host_mac,
+            })
+        );
+        assert!(matches!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted { session_id: 3, .. })
+        ));
+        assert_eq!(
+            server.sessions().collect::<Vec<_>>(),
+            vec![(host_mac, 2), (host_mac, 3)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_ppp_max_payload() -> Result<()> {
+        let tags: Vec<PppoeTag> = vec![PppoeVal::PppMaxPayload(1500).into()];
+
+        let mut buf = Vec::new();
+        tags.serialize(&mut buf)?;
+
+        assert_eq!(&buf, &[0x01, 0x20, 0x00, 0x02, 0x05, 0xdc]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_ppp_max_payload() -> Result<()> {
+        let mut tags: Vec<PppoeTag> = Vec::new();
+
+        let buf = [0x01, 0x20, 0x00, 0x02, 0x05, 0xdc];
+        tags.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(tags, vec![PppoeVal::PppMaxPayload(1500).into()]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_malformed_ppp_max_payload() -> Result<()> {
+        let mut pado = PppoePkt::default();
+
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+            0x11, 0x07, 0x00, 0x00, 0x00, 0x0f, 0x01, 0x02, 0x00, 0x00, 0x01, 0x20, 0x00, 0x00,
+            0x01, 0x20, 0x00, 0x03, 0x05, 0xdc, 0x00,
+        ];
+        pado.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            pado,
+            PppoePkt::new_pado(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                vec![
+                    PppoeVal::AcName(String::new()).into(),
+                    PppoeVal::Unhandled(0x0120, Vec::new()).into(),
+                    PppoeVal::Unhandled(0x0120, vec![0x05, 0xdc, 0x00]).into(),
+                ]
+            )
+        );
+
+        let mut out = Vec::new();
+        pado.serialize(&mut out)?;
+        assert_eq!(&out, &buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_mtu() -> Result<()> {
+        assert_eq!(session_mtu(None, None), 1492);
+        assert_eq!(session_mtu(None, Some(1500)), 1492);
+        assert_eq!(session_mtu(Some(1500), None), 1500);
+        assert_eq!(session_mtu(Some(1500), Some(1500)), 1500);
+        assert_eq!(session_mtu(Some(1500), Some(1480)), 1480);
+        assert_eq!(session_mtu(Some(1400), Some(1500)), 1492);
+        assert_eq!(session_mtu(Some(9000), Some(1500)), 1500);
+        Ok(())
+    }
+
+    #[test]
+    fn test_discovery_negotiates_ppp_max_payload() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let mut config = discovery::Config::new(host_mac);
+        config.ppp_max_payload = Some(1508);
+
+        let mut ac_config = ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec());
+        ac_config.ppp_max_payload = Some(1500);
+
+        let now = std::time::Instant::now();
+        let mut client = discovery::Client::new(config);
+        let mut server = ac::Server::new(ac_config);
+        client.start(now);
+
+        while let Some(pkt) = client.poll_transmit() {
+            server.handle_packet(&pkt, now);
+            while let Some(pkt) = server.poll_transmit() {
+                if let PppoeData::Pads(pads) = &pkt.data {
+                    assert_eq!(pads.ppp_max_payload(), Some(1500));
+                }
+
+                client.handle_packet(&pkt, now);
+            }
+        }
+
+        assert!(client.is_established());
+        assert_eq!(client.ppp_max_payload(), Some(1500));
+        assert_eq!(session_mtu(client.ppp_max_payload(), Some(1500)), 1500);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_padg() -> Result<()> {
+        let padg = PppoePkt::new_padg(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            1,
+            vec![
+                PppoeVal::Credits(Credits {
+                    fcn: 0x1337,
+                    bcn: 0x4141,
+                })
+                .into(),
+                PppoeVal::SequenceNumber(1).into(),
+            ],
+        );
+
+        let mut buf = Vec::new();
+        padg.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x63,
+                0x11, 0x0a, 0x00, 0x01, 0x00, 0x0e, 0x01, 0x06, 0x00, 0x04, 0x13, 0x37, 0x41, 0x41,
+                0x01, 0x08, 0x00, 0x02, 0x00, 0x01
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_padg() -> Result<()> {
+        let mut padg = PppoePkt::default();
+
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x63,
+            0x11, 0x0a, 0x00, 0x01, 0x00, 0x0e, 0x01, 0x06, 0x00, 0x04, 0x13, 0x37, 0x41, 0x41,
+            0x01, 0x08, 0x00, 0x02, 0x00, 0x01,
+        ];
+        padg.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            padg,
+            PppoePkt::new_padg(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                1,
+                vec![
+                    PppoeVal::Credits(Credits {
+                        fcn: 0x1337,
+                        bcn: 0x4141
+                    })
+                    .into(),
+                    PppoeVal::SequenceNumber(1).into()
+                ]
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_malformed_credits() -> Result<()> {
+        let mut tags: Vec<PppoeTag> = Vec::new();
+
+        let buf = [
+            0x01, 0x06, 0x00, 0x02, 0x13, 0x37, 0x01, 0x09, 0x00, 0x00, 0x01, 0x08, 0x00, 0x03,
+            0x00, 0x01, 0x00, 0x01, 0x07, 0x00, 0x02, 0x00, 0x00,
+        ];
+        tags.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            tags,
+            vec![
+                PppoeVal::Unhandled(0x0106, vec![0x13, 0x37]).into(),
+                PppoeVal::Unhandled(0x0109, Vec::new()).into(),
+                PppoeVal::Unhandled(0x0108, vec![0x00, 0x01, 0x00]).into(),
+                PppoeVal::Unhandled(0x0107, vec![0x00, 0x00]).into(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_padc() -> Result<()> {
+        let padc = PppoePkt::new_padc(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+            1,
+            vec![
+                PppoeVal::Credits(Credits {
+                    fcn: 0,
+                    bcn: 0x1337,
+                })
+                .into(),
+                PppoeVal::SequenceNumber(1).into(),
+            ],
+        );
+
+        let mut buf = Vec::new();
+        padc.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+                0x11, 0x0b, 0x00, 0x01, 0x00, 0x0e, 0x01, 0x06, 0x00, 0x04, 0x00, 0x00, 0x13, 0x37,
+                0x01, 0x08, 0x00, 0x02, 0x00, 0x01
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_padc() -> Result<()> {
+        let mut padc = PppoePkt::default();
+
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+            0x11, 0x0b, 0x00, 0x01, 0x00, 0x0e, 0x01, 0x06, 0x00, 0x04, 0x00, 0x00, 0x13, 0x37,
+            0x01, 0x08, 0x00, 0x02, 0x00, 0x01,
+        ];
+        padc.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            padc,
+            PppoePkt::new_padc(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                1,
+                vec![
+                    PppoeVal::Credits(Credits {
+                        fcn: 0,
+                        bcn: 0x1337
+                    })
+                    .into(),
+                    PppoeVal::SequenceNumber(1).into()
+                ]
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_metrics() -> Result<()> {
+        let tags: Vec<PppoeTag> = vec![
+            PppoeVal::Metrics(Metrics {
+                receive_only: true,
+                rlq: 90,
+                resources: 100,
+                latency: 20,
+                cur_data_rate: 5000,
+                max_data_rate: 10000,
+            })
+            .into(),
+            PppoeVal::CreditScaleFactor(64).into(),
+        ];
+
+        let mut buf = Vec::new();
+        tags.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x01, 0x07, 0x00, 0x0a, 0x00, 0x01, 0x5a, 0x64, 0x00, 0x14, 0x13, 0x88, 0x27, 0x10,
+                0x01, 0x09, 0x00, 0x02, 0x00, 0x40
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_metrics() -> Result<()> {
+        let mut tags: Vec<PppoeTag> = Vec::new();
+
+        let buf = [
+            0x01, 0x07, 0x00, 0x0a, 0x00, 0x01, 0x5a, 0x64, 0x00, 0x14, 0x13, 0x88, 0x27, 0x10,
+            0x01, 0x09, 0x00, 0x02, 0x00, 0x40,
+        ];
+        tags.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            tags,
+            vec![
+                PppoeVal::Metrics(Metrics {
+                    receive_only: true,
+                    rlq: 90,
+                    resources: 100,
+                    latency: 20,
+                    cur_data_rate: 5000,
+                    max_data_rate: 10000,
+                })
+                .into(),
+                PppoeVal::CreditScaleFactor(64).into(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_credit_accounting() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let mut host = credits::Account::default();
+        let mut ac = credits::Account::default();
+
+        let padg = ac.grant(host_mac, ac_mac, 1, 10);
+        assert!(ac.is_pending());
+        assert_eq!(ac.rx_credits(), 10);
+
+        let padc = host.handle_padg(&padg, 5).expect("padg carries credits");
+        assert_eq!(host.tx_credits(), 10);
+        assert_eq!(host.rx_credits(), 5);
+
+        assert!(ac.handle_padc(&padc));
+        assert!(!ac.is_pending());
+        assert_eq!(ac.tx_credits(), 5);
+        assert_eq!(ac.rx_credits(), 10);
+
+        assert_eq!(host.credits_for(1492), 24);
+        assert!(!host.try_send(1492));
+        assert!(host.try_send(600));
+        assert_eq!(host.tx_credits(), 0);
+
+        assert!(ac.received(600));
+        assert_eq!(ac.rx_credits(), 0);
+        assert!(!ac.handle_padc(&padc));
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_padm() -> Result<()> {
+        let padm = PppoePkt::new_padm(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+            1,
+            vec![
+                PppoeVal::Motm("hi".into()).into(),
+                PppoeVal::Hurl("http://a".into()).into(),
+            ],
+        );
+
+        let mut buf = Vec::new();
+        padm.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+                0x11, 0xd3, 0x00, 0x01, 0x00, 0x12, 0x01, 0x12, 0x00, 0x02, 0x68, 0x69, 0x01, 0x11,
+                0x00, 0x08, 0x68, 0x74, 0x74, 0x70, 0x3a, 0x2f, 0x2f, 0x61
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_padm() -> Result<()> {
+        let mut padm = PppoePkt::default();
+
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+            0x11, 0xd3, 0x00, 0x01, 0x00, 0x12, 0x01, 0x12, 0x00, 0x02, 0x68, 0x69, 0x01, 0x11,
+            0x00, 0x08, 0x68, 0x74, 0x74, 0x70, 0x3a, 0x2f, 0x2f, 0x61,
+        ];
+        padm.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            padm,
+            PppoePkt::new_padm(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                1,
+                vec![
+                    PppoeVal::Motm("hi".into()).into(),
+                    PppoeVal::Hurl("http://a".into()).into()
+                ]
+            )
+        );
+
+        if let PppoeData::Padm(padm) = &padm.data {
+            assert_eq!(padm.motms().collect::<Vec<_>>(), vec!["hi"]);
+            assert_eq!(padm.hurls().collect::<Vec<_>>(), vec!["http://a"]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_padn() -> Result<()> {
+        let padn = PppoePkt::new_padn(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+            1,
+            vec![PppoeVal::IpRouteAdd(IpRoute {
+                dst_prefix: Ipv4Addr::new(192, 0, 2, 0).into(),
+                prefix_len: 24,
+                gateway: Ipv4Addr::new(198, 51, 100, 1).into(),
+                metric: 1,
+            })
+            .into()],
+        );
+
+        let mut buf = Vec::new();
+        padn.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+                0x11, 0xd4, 0x00, 0x01, 0x00, 0x14, 0x01, 0x21, 0x00, 0x10, 0xc0, 0x00, 0x02, 0x00,
+                0x00, 0x00, 0x00, 0x18, 0xc6, 0x33, 0x64, 0x01, 0x00, 0x00, 0x00, 0x01
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_padn() -> Result<()> {
+        let mut padn = PppoePkt::default();
+
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+            0x11, 0xd4, 0x00, 0x01, 0x00, 0x14, 0x01, 0x21, 0x00, 0x10, 0xc0, 0x00, 0x02, 0x00,
+            0x00, 0x00, 0x00, 0x18, 0xc6, 0x33, 0x64, 0x01, 0x00, 0x00, 0x00, 0x01,
+        ];
+        padn.deserialize(&mut buf.as_ref())?;
+
+        let route = IpRoute {
+            dst_prefix: Ipv4Addr::new(192, 0, 2, 0).into(),
+            prefix_len: 24,
+            gateway: Ipv4Addr::new(198, 51, 100, 1).into(),
+            metric: 1,
+        };
+
+        assert_eq!(
+            padn,
+            PppoePkt::new_padn(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                1,
+                vec![PppoeVal::IpRouteAdd(route).into()]
+            )
+        );
+
+        if let PppoeData::Padn(padn) = &padn.data {
+            assert_eq!(padn.routes().collect::<Vec<_>>(), vec![&route]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_vendor_specific() -> Result<()> {
+        let tags: Vec<PppoeTag> = vec![
+            PppoeVal::VendorSpecific(VendorSpecific::Bbf(vec![
+                BbfOpt::AgentCircuitId("eth0".into()).into(),
+                BbfOpt::AgentRemoteId("sub".into()).into(),
+                BbfOpt::ActualDataRateUpstream(1000).into(),
+                BbfOpt::AccessLoopEncapsulation(AccessLoopEncapsulation {
+                    data_link: 0x01,
+                    encaps1: 0x00,
+                    encaps2: 0x00,
+                })
+                .into(),
+            ]))
+            .into(),
+            PppoeVal::VendorSpecific(VendorSpecific::Unhandled(9, vec![13, 37])).into(),
+        ];
+
+        let mut buf = Vec::new();
+        tags.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x01, 0x05, 0x00, 0x1a, 0x00, 0x00, 0x0d, 0xe9, 0x01, 0x04, 0x65, 0x74, 0x68, 0x30,
+                0x02, 0x03, 0x73, 0x75, 0x62, 0x81, 0x04, 0x00, 0x00, 0x03, 0xe8, 0x90, 0x03, 0x01,
+                0x00, 0x00, 0x01, 0x05, 0x00, 0x06, 0x00, 0x00, 0x00, 0x09, 0x0d, 0x25,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_vendor_specific() -> Result<()> {
+        let mut tags: Vec<PppoeTag> = Vec::new();
+
+        let buf = [
+            0x01, 0x05, 0x00, 0x1e, 0x00, 0x00, 0x0d, 0xe9, 0x01, 0x04, 0x65, 0x74, 0x68, 0x30,
+            0x02, 0x03, 0x73, 0x75, 0x62, 0x81, 0x04, 0x00, 0x00, 0x03, 0xe8, 0x90, 0x03, 0x01,
+            0x00, 0x00, 0x8e, 0x02, 0x13, 0x37, 0x01, 0x05, 0x00, 0x06, 0x00, 0x00, 0x00, 0x09,
+            0x0d, 0x25,
+        ];
+        tags.deserialize(&mut buf.as_ref())?;
+
+        let bbf = VendorSpecific::Bbf(vec![
+            BbfOpt::AgentCircuitId("eth0".into()).into(),
+            BbfOpt::AgentRemoteId("sub".into()).into(),
+            BbfOpt::ActualDataRateUpstream(1000).into(),
+            BbfOpt::AccessLoopEncapsulation(AccessLoopEncapsulation {
+                data_link: 0x01,
+                encaps1: 0x00,
+                encaps2: 0x00,
+            })
+            .into(),
+            BbfOpt::Unhandled(0x8e, vec![0x13, 0x37]).into(),
+        ]);
+
+        assert_eq!(
+            tags,
+            vec![
+                PppoeVal::VendorSpecific(bbf.clone()).into(),
+                PppoeVal::VendorSpecific(VendorSpecific::Unhandled(9, vec![13, 37])).into(),
+            ]
+        );
+        assert_eq!(bbf.agent_circuit_id().as_deref(), Some("eth0"));
+        assert_eq!(bbf.agent_remote_id().as_deref(), Some("sub"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_non_utf8_agent_id() -> Result<()> {
+        let mut padi = PppoePkt::default();
+
+        let buf = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x63,
+            0x11, 0x09, 0x00, 0x00, 0x00, 0x15, 0x01, 0x01, 0x00, 0x00, 0x01, 0x05, 0x00, 0x0d,
+            0x00, 0x00, 0x0d, 0xe9, 0x01, 0x02, 0xff, 0xfe, 0x02, 0x03, 0x73, 0x75, 0x62,
+        ];
+        padi.deserialize(&mut buf.as_ref())?;
+
+        let bbf = VendorSpecific::Bbf(vec![
+            BbfOpt::AgentCircuitId(vec![0xff, 0xfe]).into(),
+            BbfOpt::AgentRemoteId("sub".into()).into(),
+        ]);
+
+        assert_eq!(
+            padi,
+            PppoePkt::new_padi(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::VendorSpecific(bbf.clone()).into(),
+                ]
+            )
+        );
+        assert_eq!(bbf.agent_circuit_id().as_deref(), Some("\u{fffd}\u{fffd}"));
+        assert_eq!(bbf.agent_remote_id().as_deref(), Some("sub"));
+
+        let mut out = Vec::new();
+        padi.serialize(&mut out)?;
+        assert_eq!(&out, &buf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_reports_line_id() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let now = std::time::Instant::now();
+        let server_config = ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec());
+        let mut server = ac::Server::new(server_config);
+
+        server.handle_packet(
+            &PppoePkt::new_padr(
+                ac_mac,
+                host_mac,
+                vec![
+                    PppoeVal::ServiceName(String::new()).into(),
+                    PppoeVal::AcCookie(server.cookie(host_mac)).into(),
+                    PppoeVal::VendorSpecific(VendorSpecific::Bbf(vec![
+                        BbfOpt::AgentCircuitId("eth0".into()).into(),
+                        BbfOpt::AgentRemoteId("sub".into()).into(),
+                    ]))
+                    .into(),
+                ],
+            ),
+            now,
+        );
+
+        assert_eq!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted {
+                session_id: 1,
+                client_mac: host_mac,
+                service_name: String::new(),
+                agent_circuit_id: Some("eth0".into()),
+                agent_remote_id: Some("sub".into()),
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_relay_forwards_discovery() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+        let relay_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x03].into();
+
+        let line_id: Vec<BbfOption> = vec![
+            BbfOpt::AgentCircuitId("eth0".into()).into(),
+            BbfOpt::AgentRemoteId("sub".into()).into(),
+        ];
+
+        let now = std::time::Instant::now();
+        let subscriber_vlans = vec![VlanTag::new_outer(100), VlanTag::new(7)];
+        let ac_vlans = vec![VlanTag::new(42)];
+
+        let mut client = discovery::Client::new(discovery::Config {
+            vlans: subscriber_vlans.clone(),
+            ..discovery::Config::new(host_mac)
+        });
+        let mut server =
+            ac::Server::new(ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec()));
+
+        let mut relay = relay::Relay::new(relay::Config {
+            ac_vlans: ac_vlans.clone(),
+            ..relay::Config::new(relay_mac)
+        });
+        client.start(now);
+
+        let mut pads = None;
+        while let Some(pkt) = client.poll_transmit() {
+            assert_eq!(pkt.vlans, subscriber_vlans);
+
+            relay.handle_subscriber_packet(&pkt, &line_id);
+            while let Some((side, pkt)) = relay.poll_transmit() {
+                assert_eq!(side, relay::Side::Ac);
+                assert_eq!(pkt.src_mac, relay_mac);
+                assert_eq!(pkt.vlans, ac_vlans);
+
+                server.handle_packet(&pkt, now);
+                while let Some(pkt) = server.poll_transmit() {
+                    relay.handle_ac_packet(&pkt);
+                    while let Some((side, pkt)) = relay.poll_transmit() {
+                        assert_eq!(side, relay::Side::Subscriber);
+                        assert_eq!(pkt.dst_mac, host_mac);
+                        assert_eq!(pkt.vlans, subscriber_vlans);
+
+                        if let PppoeData::Pads(data) = &pkt.data {
+                            pads = Some(data.clone());
+                        }
+
+                        client.handle_packet(&pkt, now);
+                    }
+                }
+            }
+        }
+
+        assert_eq!(
+            client.poll_event(),
+            Some(discovery::Event::SessionEstablished {
+                session_id: 1,
+                ac_mac: relay_mac
+            })
+        );
+        assert_eq!(
+            server.poll_event(),
+            Some(ac::Event::SessionStarted {
+                session_id: 1,
+                client_mac: relay_mac,
+                service_name: String::new(),
+                agent_circuit_id: Some("eth0".into()),
+                agent_remote_id: Some("sub".into()),
+            })
+        );
+        assert!(!pads
+            .unwrap()
+            .tags
+            .iter()
+            .any(|tag| matches!(tag.data, PppoeVal::RelaySessionId(_))));
+        assert_eq!(
+            relay.sessions().collect::<Vec<_>>(),
+            vec![(host_mac, ac_mac, 1)]
+        );
+
+        relay.handle_ac_packet(&PppoePkt::new_ppp(
+            relay_mac,
+            ac_mac,
+            1,
+            PppPkt::new_lcp(LcpPkt::new_echo_request(0, 0, Vec::new())),
+        ));
+        let (side, ppp) = relay.poll_transmit().unwrap();
+        assert_eq!(side, relay::Side::Subscriber);
+        assert_eq!(ppp.vlans, subscriber_vlans);
+
+        relay.handle_subscriber_packet(
+            &PppoePkt::new_padt(relay_mac, host_mac, 1, Vec::new())
+                .with_vlans(subscriber_vlans.clone()),
+            &line_id,
+        );
+        let (side, padt) = relay.poll_transmit().unwrap();
+        assert_eq!(side, relay::Side::Ac);
+        assert_eq!(padt.vlans, ac_vlans);
+
+        server.handle_packet(&padt, now);
+        assert_eq!(
+            server.poll_event(),
+            Some(ac::Event::SessionTerminated {
+                session_id: 1,
+                client_mac: relay_mac,
+            })
+        );
+        assert_eq!(relay.sessions().count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_relay_drops_unknown_relay_session_id() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+        let relay_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x03].into();
+
+        let mut relay = relay::Relay::new(relay::Config::new(relay_mac));
+
+        relay.handle_ac_packet(&PppoePkt::new_pado(
+            relay_mac,
+            ac_mac,
+            vec![PppoeVal::RelaySessionId(vec![0, 0, 0, 1]).into()],
+        ));
+        assert_eq!(relay.poll_transmit(), None);
+
+        relay.handle_subscriber_packet(
+            &PppoePkt::new_padr(
+                relay_mac,
+                host_mac,
+                vec![PppoeVal::RelaySessionId(vec![0, 0, 0, 1]).into()],
+            ),
+            &[],
+        );
+        assert_eq!(relay.poll_transmit(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_unhandled_tag() -> Result<()> {
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x88, 0x63,
+            0x11, 0x07, 0x00, 0x00, 0x00, 0x0a, 0x01, 0x02, 0x00, 0x00, 0x01, 0xfe, 0x00, 0x02,
+            0x13, 0x37,
+        ];
+
+        let mut pado = PppoePkt::default();
+        pado.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            pado,
+            PppoePkt::new_pado(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                vec![
+                    PppoeVal::AcName(String::new()).into(),
+                    PppoeVal::Unhandled(0x01fe, vec![0x13, 0x37]).into(),
+                ]
+            )
+        );
+
+        let mut out = Vec::new();
+        pado.serialize(&mut out)?;
+        assert_eq!(&out, &buf);
+
+        let mut strict = PppoePkt::default();
+        assert!(matches!(
+            strict.deserialize_strict(&mut buf.as_ref()),
+            Err(Error::InvalidPppoeTag(0x01fe))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_pppoe_qinq() -> Result<()> {
+        let padi = PppoePkt::new_padi(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            vec![PppoeVal::ServiceName(String::new()).into()],
+        )
+        .with_vlans(vec![
+            VlanTag::new_outer(100),
+            VlanTag {
+                tpid: ETHER_TYPE_VLAN,
+                pcp: 5,
+                dei: true,
+                vid: 7,
+            },
+        ]);
+
+        let mut buf = Vec::new();
+        padi.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0xa8,
+                0x00, 0x64, 0x81, 0x00, 0xb0, 0x07, 0x88, 0x63, 0x11, 0x09, 0x00, 0x00, 0x00, 0x04,
+                0x01, 0x01, 0x00, 0x00,
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_pppoe_qinq() -> Result<()> {
+        let mut padi = PppoePkt::default();
+
+        let buf = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0xa8,
+            0x00, 0x64, 0x81, 0x00, 0xb0, 0x07, 0x88, 0x63, 0x11, 0x09, 0x00, 0x00, 0x00, 0x04,
+            0x01, 0x01, 0x00, 0x00,
+        ];
+        padi.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            padi,
+            PppoePkt::new_padi(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                vec![PppoeVal::ServiceName(String::new()).into()],
+            )
+            .with_vlans(vec![
+                VlanTag::new_outer(100),
+                VlanTag {
+                    tpid: ETHER_TYPE_VLAN,
+                    pcp: 5,
+                    dei: true,
+                    vid: 7,
+                },
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ac_replies_on_client_vlan() -> Result<()> {
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let mut config = discovery::Config::new(host_mac);
+        config.vlans = vec![VlanTag::new(7)];
+
+        let now = std::time::Instant::now();
+        let mut client = discovery::Client::new(config);
+        let mut server =
+            ac::Server::new(ac::Config::new(ac_mac, "isp_ac".into(), b"secret".to_vec()));
+        client.start(now);
+
+        while let Some(pkt) = client.poll_transmit() {
+            assert_eq!(pkt.vlans, vec![VlanTag::new(7)]);
+
+            server.handle_packet(&pkt, now);
+            while let Some(pkt) = server.poll_transmit() {
+                assert_eq!(pkt.vlans, vec![VlanTag::new(7)]);
+                client.handle_packet(&pkt, now);
+            }
+        }
+
+        assert!(client.is_established());
+
+        server.terminate(1);
+        assert_eq!(server.poll_transmit().unwrap().vlans, vec![VlanTag::new(7)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_discovery() -> Result<()> {
+        use validate::Violation;
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let service_name: PppoeTag = PppoeVal::ServiceName(String::new()).into();
+
+        let mut padi = PppoePkt::new_padi(host_mac, vec![service_name.clone()]);
+        assert_eq!(padi.validate(), Ok(()));
+
+        padi.ver_type = VerType(0x21);
+        assert_eq!(padi.validate(), Err(Violation::InvalidVerType(0x21)));
+
+        let mut padi = PppoePkt::new_padi(host_mac, vec![service_name.clone()]);
+        padi.dst_mac = ac_mac;
+        assert_eq!(padi.validate(), Err(Violation::NotBroadcast));
+
+        let padi = PppoePkt::new_padi(host_mac, Vec::new());
+        assert_eq!(padi.validate(), Err(Violation::ServiceNameCount(0)));
+
+        let padi = PppoePkt::new_padi(
+            host_mac,
+            vec![
+                service_name.clone(),
+                PppoeVal::HostUniq(vec![0; 1480]).into(),
+            ],
+        );
+        assert_eq!(padi.validate(), Err(Violation::PayloadTooLong(1488)));
+
+        let pado = PppoePkt::new_pado(host_mac, ac_mac, vec![service_name.clone()]);
+        assert_eq!(pado.validate(), Err(Violation::MissingAcName));
+
+        let pads = PppoePkt::new_pads(host_mac, ac_mac, 0xffff, vec![service_name.clone()]);
+        assert_eq!(pads.validate(), Err(Violation::InvalidSessionId(0xffff)));
+
+        let pads = PppoePkt::new_pads(
+            host_mac,
+            ac_mac,
+            0,
+            vec![
+                service_name,
+                PppoeVal::ServiceNameError(String::new()).into(),
+            ],
+        );
+        assert_eq!(pads.validate(), Ok(()));
+
+        let padt = PppoePkt::new_padt(ac_mac, host_mac, 0, Vec::new());
+        assert_eq!(padt.validate(), Err(Violation::InvalidSessionId(0)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_ver_type() -> Result<()> {
+        use validate::Violation;
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let mut padi =
+            PppoePkt::new_padi(host_mac, vec![PppoeVal::ServiceName(String::new()).into()]);
+
+        padi.ver_type = VerType(0x21);
+        assert_eq!(padi.validate(), Err(Violation::InvalidVerType(0x21)));
+
+        padi.ver_type = VerType(0x12);
+        assert_eq!(padi.validate(), Err(Violation::InvalidVerType(0x12)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_unknown_code() -> Result<()> {
+        use validate::Violation;
+
+        let mut pkt = PppoePkt::default();
+
+        let buf = [
+            0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x63,
+            0x11, 0x42, 0x00, 0x00, 0x00, 0x00,
+        ];
+        pkt.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(pkt.data, PppoeData::Ignore);
+        assert_eq!(pkt.validate(), Err(Violation::UnknownCode));
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let mut padi =
+            PppoePkt::new_padi(host_mac, vec![PppoeVal::ServiceName(String::new()).into()]);
+        padi.ether_type = EtherType::PppoeSession;
+        assert_eq!(padi.validate(), Err(Violation::EtherTypeMismatch));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_padi() -> Result<()> {
+        use validate::Violation;
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let service_name: PppoeTag = PppoeVal::ServiceName(String::new()).into();
+
+        let mut padi = PppoePkt::new_padi(host_mac, vec![service_name.clone()]);
+        padi.dst_mac = ac_mac;
+        assert_eq!(padi.validate(), Err(Violation::NotBroadcast));
+
+        let mut padi = PppoePkt::new_padi(host_mac, vec![service_name.clone()]);
+        padi.session_id = 1;
+        assert_eq!(padi.validate(), Err(Violation::InvalidSessionId(1)));
+
+        let padi = PppoePkt::new_padi(host_mac, Vec::new());
+        assert_eq!(padi.validate(), Err(Violation::ServiceNameCount(0)));
+
+        let padi = PppoePkt::new_padi(host_mac, vec![service_name.clone(), service_name]);
+        assert_eq!(padi.validate(), Err(Violation::ServiceNameCount(2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_pado() -> Result<()> {
+        use validate::Violation;
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let ac_name: PppoeTag = PppoeVal::AcName("isp_ac".into()).into();
+        let service_name: PppoeTag = PppoeVal::ServiceName(String::new()).into();
+
+        let pado = PppoePkt::new_pado(
+            host_mac,
+            ac_mac,
+            vec![ac_name.clone(), service_name.clone()],
+        );
+        assert_eq!(pado.validate(), Ok(()));
+
+        let pado = PppoePkt::new_pado(host_mac, ac_mac, vec![service_name.clone()]);
+        assert_eq!(pado.validate(), Err(Violation::MissingAcName));
+
+        let pado = PppoePkt::new_pado(host_mac, ac_mac, vec![ac_name.clone()]);
+        assert_eq!(pado.validate(), Err(Violation::MissingServiceName));
+
+        let pado = PppoePkt::new_pado(MacAddr::BROADCAST, ac_mac, vec![ac_name, service_name]);
+        assert_eq!(pado.validate(), Err(Violation::UnexpectedBroadcast));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_pads() -> Result<()> {
+        use validate::Violation;
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+        let ac_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into();
+
+        let service_name: PppoeTag = PppoeVal::ServiceName(String::new()).into();
+        let error: PppoeTag = PppoeVal::AcSystemError(String::new()).into();
+
+        let pads = PppoePkt::new_pads(host_mac, ac_mac, 1, vec![service_name.clone()]);
+        assert_eq!(pads.validate(), Ok(()));
+
+        let pads = PppoePkt::new_pads(host_mac, ac_mac, 0, vec![service_name.clone()]);
+        assert_eq!(pads.validate(), Err(Violation::InvalidSessionId(0)));
+
+        let pads = PppoePkt::new_pads(host_mac, ac_mac, 0xffff, vec![service_name.clone()]);
+        assert_eq!(pads.validate(), Err(Violation::InvalidSessionId(0xffff)));
+
+        let pads = PppoePkt::new_pads(
+            host_mac,
+            ac_mac,
+            1,
+            vec![service_name.clone(), error.clone()],
+        );
+        assert_eq!(pads.validate(), Err(Violation::InvalidSessionId(1)));
+
+        let pads = PppoePkt::new_pads(host_mac, ac_mac, 0, vec![service_name, error]);
+        assert_eq!(pads.validate(), Ok(()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_pppoe_payload_limit() -> Result<()> {
+        use validate::Violation;
+
+        let host_mac = [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into();
+
+        let service_name: PppoeTag = PppoeVal::ServiceName(String::new()).into();
+
+        let padi = PppoePkt::new_padi(
+            host_mac,
+            vec![
+                service_name.clone(),
+                PppoeVal::HostUniq(vec![0; 1476]).into(),
+            ],
+        );
+        assert_eq!(padi.validate(), Ok(()));
+
+        let padi = PppoePkt::new_padi(
+            host_mac,
+            vec![service_name, PppoeVal::HostUniq(vec![0; 1477]).into()],
+        );
+        assert_eq!(padi.validate(), Err(Violation::PayloadTooLong(1485)));
+        Ok(())
+    }
+
+    struct TestIpcp {
+        addr: Ipv4Addr,
+    }
+
+    impl ControlProtocol for TestIpcp {
+        type Packet = IpcpPkt;
+
+        fn request_options(&mut self) -> Vec<IpcpOption> {
+            vec![IpcpOpt::IpAddr(self.addr.into()).into()]
+        }
+
+        fn check_request(&mut self, _options: &[IpcpOption]) -> FsmVerdict<IpcpOption> {
+            FsmVerdict::Ack
+        }
+    }
+
+    fn exchange(a: &mut Fsm<TestIpcp>, b: &mut Fsm<TestIpcp>, now: std::time::Instant) {
+        loop {
+            let mut idle = true;
+
+            while let Some(pkt) = a.poll_transmit() {
+                b.handle_packet(&pkt, now);
+                idle = false;
+            }
+            while let Some(pkt) = b.poll_transmit() {
+                a.handle_packet(&pkt, now);
+                idle = false;
+            }
+
+            if idle {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_fsm_open_close() -> Result<()> {
+        let now = std::time::Instant::now();
+
+        let mut a = Fsm::new(
+            TestIpcp {
+                addr: Ipv4Addr::new(198, 51, 100, 1),
+            },
+            FsmConfig::default(),
+        );
+        let mut b = Fsm::new(
+            TestIpcp {
+                addr: Ipv4Addr::new(198, 51, 100, 2),
+            },
+            FsmConfig::default(),
+        );
+
+        a.open(now);
+        assert_eq!(a.state(), FsmState::Starting);
+        assert_eq!(a.poll_action(), Some(FsmAction::ThisLayerStarted));
+
+        b.up(now);
+        b.open(now);
+        a.up(now);
+        assert_eq!(a.state(), FsmState::ReqSent);
+
+        exchange(&mut a, &mut b, now);
+
+        assert!(a.is_opened());
+        assert!(b.is_opened());
+        assert_eq!(a.poll_action(), Some(FsmAction::ThisLayerUp));
+        assert_eq!(b.poll_action(), Some(FsmAction::ThisLayerUp));
+        assert_eq!(a.poll_timeout(), None);
+
+        a.close(now);
+        assert_eq!(a.state(), FsmState::Closing);
+        exchange(&mut a, &mut b, now);
+
+        assert_eq!(a.state(), FsmState::Closed);
+        assert_eq!(a.poll_action(), Some(FsmAction::ThisLayerDown));
+        assert_eq!(a.poll_action(), Some(FsmAction::ThisLayerFinished));
+
+        assert_eq!(b.state(), FsmState::Stopping);
+        assert_eq!(b.poll_action(), Some(FsmAction::ThisLayerDown));
+
+        let deadline = b.poll_timeout().unwrap();
+        b.handle_timeout(deadline);
+        assert_eq!(b.state(), FsmState::Stopped);
+        assert_eq!(b.poll_action(), Some(FsmAction::ThisLayerFinished));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fsm_max_configure() -> Result<()> {
+        let now = std::time::Instant::now();
+
+        let mut fsm = Fsm::new(
+            TestIpcp {
+                addr: Ipv4Addr::new(198, 51, 100, 1),
+            },
+            FsmConfig::default(),
+        );
+        fsm.up(now);
+        fsm.open(now);
+
+        let mut requests = 0;
+        while let Some(deadline) = fsm.poll_timeout() {
+            while let Some(pkt) = fsm.poll_transmit() {
+                assert!(matches!(pkt.data, IpcpData::ConfigureRequest(_)));
+                requests += 1;
+            }
+
+            fsm.handle_timeout(deadline);
+        }
+
+        assert_eq!(requests, 10);
+        assert_eq!(fsm.state(), FsmState::Stopped);
+        assert_eq!(fsm.poll_action(), Some(FsmAction::ThisLayerFinished));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fsm_code_reject() -> Result<()> {
+        let now = std::time::Instant::now();
+
+        let mut fsm = Fsm::new(
+            TestIpcp {
+                addr: Ipv4Addr::new(198, 51, 100, 1),
+            },
+            FsmConfig::default(),
+        );
+        fsm.up(now);
+
+        fsm.handle_packet(
+            &IpcpPkt {
+                identifier: 7,
+                data: IpcpData::Unhandled(42, vec![13, 37]),
+            },
+            now,
+        );
+
+        assert_eq!(
+            fsm.poll_transmit(),
+            Some(IpcpPkt::new_code_reject(0, vec![42, 7, 0, 6, 13, 37]))
+        );
+        assert_eq!(fsm.state(), FsmState::Closed);
+        Ok(())
+    }
+
+    // Deterministic magic numbers: counts up from the seed.
+    struct SeqRng(u32);
+
+    impl lcp::magic::MagicRng for SeqRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0 = self.0.wrapping_add(1);
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_lcp_negotiation_reply() -> Result<()> {
+        use lcp::negotiation::{Negotiator, Policy};
+
+        let policy = Policy {
+            peer_mru: 1280..=1492,
+            peer_auth: vec![AuthProto::Chap(ChapAlgorithm::Md5)],
+            ..Default::default()
+        };
+
+        let mut negotiator = Negotiator::with_rng(policy, SeqRng(0x01020303));
+        assert_eq!(negotiator.magic_number(), 0x01020304);
+
+        let request = LcpConfigureRequest {
+            options: vec![
+                LcpOpt::Mru(1500).into(),
+                LcpOpt::Unhandled(99, vec![13, 37]).into(),
+                LcpOpt::AuthenticationProtocol(AuthProto::Pap.into()).into(),
+                LcpOpt::ProtocolFieldCompression.into(),
+            ],
+        };
+        assert_eq!(
+            negotiator.reply(1, &request),
+            LcpPkt::new_configure_reject(
+                1,
+                vec![
+                    LcpOpt::Unhandled(99, vec![13, 37]).into(),
+                    LcpOpt::ProtocolFieldCompression.into(),
+                ]
+            )
+        );
+
+        let request = LcpConfigureRequest {
+            options: vec![
+                LcpOpt::Mru(1500).into(),
+                LcpOpt::AuthenticationProtocol(AuthProto::Pap.into()).into(),
+                LcpOpt::MagicNumber(0x01020304).into(),
+            ],
+        };
+        assert_eq!(
+            negotiator.reply(2, &request),
+            LcpPkt::new_configure_nak(
+                2,
+                vec![
+                    LcpOpt::Mru(1492).into(),
+                    LcpOpt::AuthenticationProtocol(AuthProto::Chap(ChapAlgorithm::Md5).into())
+                        .into(),
+                    LcpOpt::MagicNumber(0x01020306).into(),
+                ]
+            )
+        );
+
+        let request = LcpConfigureRequest {
+            options: vec![
+                LcpOpt::Mru(1492).into(),
+                LcpOpt::AuthenticationProtocol(AuthProto::Chap(ChapAlgorithm::Md5).into()).into(),
+                LcpOpt::MagicNumber(0x05060708).into(),
+            ],
+        };
+        assert_eq!(
+            negotiator.reply(3, &request),
+            LcpPkt::new_configure_ack(3, request.options.clone())
+        );
+        assert_eq!(negotiator.peer().mru, Some(1492));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_negotiation_adjusts_request() -> Result<()> {
+        use lcp::negotiation::{Negotiator, Policy};
+
+        let policy = Policy {
+            mru: Some(1492),
+            pfc: true,
+            ..Default::default()
+        };
+
+        let mut negotiator = Negotiator::with_rng(policy, SeqRng(0x01020303));
+        assert_eq!(
+            negotiator.request_options(),
+            vec![
+                LcpOpt::Mru(1492).into(),
+                LcpOpt::MagicNumber(0x01020304).into(),
+                LcpOpt::ProtocolFieldCompression.into(),
+            ]
+        );
+
+        negotiator.handle_reject(&[LcpOpt::ProtocolFieldCompression.into()]);
+        negotiator.handle_nak(&[
+            LcpOpt::Mru(1400).into(),
+            LcpOpt::MagicNumber(0x01020304).into(),
+        ]);
+
+        let options = negotiator.request_options();
+        assert_eq!(options.len(), 2);
+        assert_eq!(options[0], LcpOpt::Mru(1400).into());
+        assert!(matches!(
+            options[1].value,
+            LcpOpt::MagicNumber(n) if n != 0 && n != 0x01020304
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_negotiation_limits_nak() -> Result<()> {
+        use lcp::negotiation::{Negotiator, Policy};
+
+        let policy = Policy {
+            mru: Some(1492),
+            mrru: Some(1524),
+            magic_number: false,
+            ..Default::default()
+        };
+
+        let mut negotiator = Negotiator::new(policy);
+
+        negotiator.handle_nak(&[LcpOpt::Mru(64).into(), LcpOpt::Mrru(65535).into()]);
+        assert_eq!(
+            negotiator.request_options(),
+            vec![LcpOpt::Mru(1280).into(), LcpOpt::Mrru(1524).into()]
+        );
+
+        negotiator.handle_nak(&[LcpOpt::Mru(65535).into(), LcpOpt::Mrru(64).into()]);
+        assert_eq!(
+            negotiator.request_options(),
+            vec![LcpOpt::Mru(1492).into(), LcpOpt::Mrru(1280).into()]
+        );
+
+        // Multilink is disabled, a suggested MRRU must not enable it.
+        let mut negotiator = Negotiator::new(Policy {
+            magic_number: false,
+            ..Default::default()
+        });
+
+        negotiator.handle_nak(&[LcpOpt::Mrru(1524).into()]);
+        assert_eq!(negotiator.request_options(), Vec::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_negotiation_fsm() -> Result<()> {
+        use lcp::negotiation::{Negotiator, Policy};
+
+        let now = std::time::Instant::now();
+
+        let client_policy = Policy {
+            peer_auth: vec![AuthProto::Pap],
+            mru: Some(1492),
+            ..Default::default()
+        };
+
+        let server_policy = Policy {
+            auth: Some(AuthProto::Pap),
+            peer_mru: 1280..=1492,
+            ..Default::default()
+        };
+
+        let mut client = Fsm::new(Negotiator::new(client_policy), FsmConfig::default());
+        let mut server = Fsm::new(Negotiator::new(server_policy), FsmConfig::default());
+
+        for fsm in [&mut client, &mut server] {
+            fsm.up(now);
+            fsm.open(now);
+        }
+
+        loop {
+            let mut idle = true;
+
+            while let Some(pkt) = client.poll_transmit() {
+                server.handle_packet(&pkt, now);
+                idle = false;
+            }
+            while let Some(pkt) = server.poll_transmit() {
+                client.handle_packet(&pkt, now);
+                idle = false;
+            }
+
+            if idle {
+                break;
+            }
+        }
+
+        assert!(client.is_opened());
+        assert!(server.is_opened());
+        assert_eq!(client.protocol().peer().auth, Some(AuthProto::Pap));
+        assert_eq!(server.protocol().local().auth, Some(AuthProto::Pap));
+        assert_eq!(server.protocol().peer().mru, Some(1492));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_keepalive() -> Result<()> {
+        use lcp::keepalive::{Config, Event, Keepalive};
+        use std::time::Duration;
+
+        let now = std::time::Instant::now();
+        let config = Config {
+            interval: Duration::from_secs(10),
+            max_missed: 2,
+        };
+
+        let mut keepalive = Keepalive::new(config, 0x01020304);
+        keepalive.start(now);
+
+        assert_eq!(
+            keepalive.poll_transmit(),
+            Some(LcpPkt::new_echo_request(0, 0x01020304, Vec::new()))
+        );
+
+        // Replies with a stale identifier or our own magic number don't count.
+        keepalive.handle_packet(&LcpPkt::new_echo_reply(7, 0x05060708, Vec::new()), now);
+        keepalive.handle_packet(&LcpPkt::new_echo_reply(0, 0x01020304, Vec::new()), now);
+        assert_eq!(keepalive.stats().received, 0);
+        assert_eq!(keepalive.poll_event(), Some(Event::Looped));
+
+        keepalive.handle_packet(
+            &LcpPkt::new_echo_reply(0, 0x05060708, Vec::new()),
+            now + Duration::from_millis(20),
+        );
+        assert_eq!(keepalive.stats().received, 1);
+        assert_eq!(keepalive.stats().rtt, Some(Duration::from_millis(20)));
+
+        keepalive.handle_packet(&LcpPkt::new_echo_request(3, 0x05060708, vec![13, 37]), now);
+        assert_eq!(
+            keepalive.poll_transmit(),
+            Some(LcpPkt::new_echo_reply(3, 0x01020304, vec![13, 37]))
+        );
+
+        let t1 = keepalive.poll_timeout().unwrap();
+        keepalive.handle_timeout(t1);
+        keepalive.handle_packet(
+            &LcpPkt::new_echo_reply(1, 0x05060708, Vec::new()),
+            t1 + Duration::from_millis(36),
+        );
+        assert_eq!(keepalive.stats().rtt, Some(Duration::from_millis(36)));
+        assert_eq!(keepalive.stats().jitter, Duration::from_millis(1));
+
+        let t2 = keepalive.poll_timeout().unwrap();
+        keepalive.handle_timeout(t2);
+        let t3 = keepalive.poll_timeout().unwrap();
+        keepalive.handle_timeout(t3);
+        assert_eq!(keepalive.missed(), 1);
+        assert_eq!(keepalive.poll_event(), None);
+
+        let t4 = keepalive.poll_timeout().unwrap();
+        keepalive.handle_timeout(t4);
+        assert_eq!(keepalive.poll_event(), Some(Event::LinkDead));
+        assert_eq!(keepalive.poll_timeout(), None);
+        assert_eq!(keepalive.stats().sent, 4);
+        assert_eq!(keepalive.stats().lost, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_keepalive_link_dead() -> Result<()> {
+        use lcp::keepalive::{Config, Event, Keepalive};
+        use std::time::Duration;
+
+        let now = std::time::Instant::now();
+        let config = Config {
+            interval: Duration::from_secs(10),
+            max_missed: 3,
+        };
+
+        let mut keepalive = Keepalive::new(config, 0x01020304);
+        keepalive.start(now);
+
+        for missed in 1..3 {
+            let timeout = keepalive.poll_timeout().unwrap();
+            keepalive.handle_timeout(timeout);
+
+            assert_eq!(keepalive.missed(), missed);
+            assert_eq!(keepalive.poll_event(), None);
+        }
+
+        // An answer resets the count.
+        keepalive.handle_packet(&LcpPkt::new_echo_reply(2, 0x05060708, Vec::new()), now);
+        assert_eq!(keepalive.missed(), 0);
+
+        // The next interval starts without an outstanding request.
+        for _ in 0..4 {
+            let timeout = keepalive.poll_timeout().unwrap();
+            keepalive.handle_timeout(timeout);
+        }
+        assert_eq!(keepalive.missed(), 3);
+        assert_eq!(keepalive.poll_event(), Some(Event::LinkDead));
+        assert_eq!(keepalive.poll_timeout(), None);
+        assert_eq!(keepalive.stats().sent, 6);
+        assert_eq!(keepalive.stats().lost, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_keepalive_wrong_identifier() -> Result<()> {
+        use lcp::keepalive::{Config, Event, Keepalive};
+        use std::time::Duration;
+
+        let now = std::time::Instant::now();
+        let config = Config {
+            interval: Duration::from_secs(10),
+            max_missed: 1,
+        };
+
+        let mut keepalive = Keepalive::new(config, 0x01020304);
+        keepalive.start(now);
+        assert_eq!(
+            keepalive.poll_transmit(),
+            Some(LcpPkt::new_echo_request(0, 0x01020304, Vec::new()))
+        );
+
+        for identifier in [1, 0xff, 0x80] {
+            keepalive.handle_packet(
+                &LcpPkt::new_echo_reply(identifier, 0x05060708, Vec::new()),
+                now,
+            );
+        }
+        assert_eq!(keepalive.stats().received, 0);
+        assert_eq!(keepalive.stats().rtt, None);
+
+        let timeout = keepalive.poll_timeout().unwrap();
+        keepalive.handle_timeout(timeout);
+        assert_eq!(keepalive.poll_event(), Some(Event::LinkDead));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_magic_loopback() -> Result<()> {
+        use lcp::magic::Magic;
+
+        let mut magic = Magic::with_rng(SeqRng(0), 2);
+        assert_eq!(magic.magic_number(), 1);
+
+        assert_eq!(magic.check_request(7), None);
+        assert_eq!(magic.check_request(0), Some(2));
+
+        // Our own number coming back is Nak'd and replaced.
+        assert_eq!(magic.check_request(1), Some(4));
+        assert_eq!(magic.magic_number(), 3);
+        assert!(!magic.is_looped());
+
+        magic.handle_nak(5);
+        assert_eq!(magic.magic_number(), 6);
+
+        assert!(magic.check_echo(6));
+        assert!(magic.is_looped());
+
+        magic.reset();
+        assert!(!magic.is_looped());
+        assert!(!magic.check_echo(7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_negotiation_loopback() -> Result<()> {
+        use lcp::negotiation::{Negotiator, Policy};
+
+        let now = std::time::Instant::now();
+
+        let policy = Policy {
+            max_loopback: 3,
+            ..Default::default()
+        };
+
+        let mut fsm = Fsm::new(
+            Negotiator::with_rng(policy, SeqRng(0x01020303)),
+            FsmConfig::default(),
+        );
+        fsm.up(now);
+        fsm.open(now);
+
+        // Every Configure-Request is received by ourselves.
+        while !fsm.protocol().is_looped() {
+            let pkt = fsm.poll_transmit().unwrap();
+            fsm.handle_packet(&pkt, now);
+        }
+
+        assert!(!fsm.is_opened());
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_ppp_ipv4() -> Result<()> {
+        let ipv4 = PppoePkt::new_ipv4(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            1,
+            vec![0x45, 0x00, 0x00, 0x14],
+        );
+
+        let mut buf = Vec::new();
+        ipv4.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x64,
+                0x11, 0x00, 0x00, 0x01, 0x00, 0x06, 0x00, 0x21, 0x45, 0x00, 0x00, 0x14
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_ppp_ipv6() -> Result<()> {
+        let mut ipv6 = PppoePkt::default();
+
+        let buf = [
+            0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x64,
+            0x11, 0x00, 0x00, 0x01, 0x00, 0x06, 0x00, 0x57, 0x60, 0x00, 0x00, 0x00,
+        ];
+        ipv6.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            ipv6,
+            PppoePkt::new_ipv6(
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+                [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+                1,
+                vec![0x60, 0x00, 0x00, 0x00]
+            )
+        );
+        assert_eq!(ipv6.ipv6(), Some([0x60, 0x00, 0x00, 0x00].as_ref()));
+        assert_eq!(ipv6.ipv4(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_protocol_reject_truncated() -> Result<()> {
+        let rejected = PppPkt {
+            data: PppData::Unhandled(0x8281, vec![1, 2, 3, 4, 5, 6, 7, 8]),
+        };
+
+        assert_eq!(
+            LcpPkt::new_protocol_reject_for(1, &rejected, 10)?,
+            LcpPkt::new_protocol_reject(1, 0x8281, vec![1, 2, 3, 4])
+        );
+        assert_eq!(
+            LcpPkt::new_protocol_reject_for(1, &rejected, 1500)?,
+            LcpPkt::new_protocol_reject(1, 0x8281, vec![1, 2, 3, 4, 5, 6, 7, 8])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_code_reject_truncated() -> Result<()> {
+        let rejected = LcpPkt {
+            identifier: 7,
+            data: LcpData::Unhandled(42, vec![13, 37, 13, 37]),
+        };
+        assert_eq!(
+            LcpPkt::new_code_reject_for(2, &rejected, 10)?,
+            LcpPkt::new_code_reject(2, vec![42, 7, 0, 8, 13, 37])
+        );
+
+        let rejected = Ipv6cpPkt {
+            identifier: 7,
+            data: Ipv6cpData::Unhandled(42, vec![13, 37]),
+        };
+        assert_eq!(
+            Ipv6cpPkt::new_code_reject_for(3, &rejected, 1500)?,
+            Ipv6cpPkt::new_code_reject(3, vec![42, 7, 0, 6, 13, 37])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_protocol_reject_rejected() -> Result<()> {
+        let rejected = PppPkt::new_ipcp(IpcpPkt::new_configure_request(
+            1,
+            vec![IpcpOpt::IpAddr(Ipv4Addr::new(198, 51, 100, 1).into()).into()],
+        ));
+
+        let protocol_reject = LcpPkt::new_protocol_reject_for(1, &rejected, 1500)?;
+        let payload = match &protocol_reject.data {
+            LcpData::ProtocolReject(payload) => payload,
+            _ => unreachable!(),
+        };
+        assert_eq!(payload.protocol, IPCP);
+        assert_eq!(payload.rejected(), rejected);
+
+        let protocol_reject = LcpPkt::new_protocol_reject_for(1, &rejected, 12)?;
+        let payload = match &protocol_reject.data {
+            LcpData::ProtocolReject(payload) => payload,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            payload.rejected(),
+            PppPkt {
+                data: PppData::Unhandled(IPCP, payload.pkt.clone())
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_protocol_reject_rejected_truncated() -> Result<()> {
+        let mut protocol_reject = LcpPkt::default();
+
+        let buf = [
+            0x08, 0x01, 0x00, 0x0d, 0x80, 0x21, 0x01, 0x01, 0x00, 0x0a, 0x03, 0x06, 0xc6,
+        ];
+        protocol_reject.deserialize(&mut buf.as_ref())?;
+
+        let payload = match &protocol_reject.data {
+            LcpData::ProtocolReject(payload) => payload,
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            payload.rejected(),
+            PppPkt {
+                data: PppData::Unhandled(IPCP, vec![0x01, 0x01, 0x00, 0x0a, 0x03, 0x06, 0xc6])
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ppp_framing_pfc() -> Result<()> {
+        let ipv4 = PppoePkt::new_ipv4(
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x02].into(),
+            [0x00, 0x00, 0x5e, 0x00, 0x53, 0x01].into(),
+            1,
+            vec![0x45, 0x00],
+        );
+        let framing = Framing {
+            pfc: true,
+            ..Default::default()
+        };
+
+        let mut buf = Vec::new();
+        ipv4.serialize_framed(&mut buf, &framing)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x00, 0x00, 0x5e, 0x00, 0x53, 0x02, 0x00, 0x00, 0x5e, 0x00, 0x53, 0x01, 0x88, 0x64,
+                0x11, 0x00, 0x00, 0x01, 0x00, 0x03, 0x21, 0x45, 0x00
+            ]
+        );
+
+        let mut pkt = PppoePkt::default();
+        pkt.deserialize_framed(&mut buf.as_slice(), &framing)?;
+        assert_eq!(pkt, ipv4);
+
+        // Compressed protocols are only accepted if negotiated.
+        assert!(matches!(
+            pkt.deserialize_framed(&mut buf.as_slice(), &Framing::default()),
+            Err(Error::UnexpectedPfc(0x21))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ppp_framing_acfc() -> Result<()> {
+        let framing = Framing {
+            acfc: true,
+            pfc: true,
+            ..Framing::hdlc()
+        };
+
+        let ipv6 = PppPkt::new_ipv6(vec![0x60]);
+        let mut buf = Vec::new();
+        ipv6.serialize_framed(&mut buf, &framing)?;
+        assert_eq!(&buf, &[0x57, 0x60]);
+        assert_eq!(ipv6.framed_len(&framing), 2);
+
+        // LCP always carries the Address and Control fields.
+        let lcp = PppPkt::new_lcp(LcpPkt::new_terminate_ack(1, Vec::new()));
+        let mut buf = Vec::new();
+        lcp.serialize_framed(&mut buf, &framing)?;
+        assert_eq!(&buf, &[0xff, 0x03, 0xc0, 0x21, 0x06, 0x01, 0x00, 0x04]);
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize_framed(&mut [0x00, 0x57, 0x60].as_ref(), &framing)?;
+        assert_eq!(pkt, ipv6);
+        pkt.deserialize_framed(&mut [0xff, 0x03, 0x57, 0x60].as_ref(), &framing)?;
+        assert_eq!(pkt, ipv6);
+
+        assert!(matches!(
+            pkt.deserialize_framed(&mut [0x00, 0x57, 0x60].as_ref(), &Framing::hdlc()),
+            Err(Error::MissingAddrCtl)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdlc_fcs() -> Result<()> {
+        assert_eq!(fcs16(b"123456789"), 0x906e);
+        assert_eq!(fcs32(b"123456789"), 0xcbf43926);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdlc_encode() -> Result<()> {
+        let encoder = HdlcEncoder::default();
+        let lcp = PppPkt::new_lcp(LcpPkt::new_terminate_ack(1, Vec::new()));
+
+        let mut frame = vec![0xff, 0x03, 0xc0, 0x21, 0x06, 0x01, 0x00, 0x04];
+        frame.extend_from_slice(&fcs16(&frame).to_le_bytes());
+
+        let mut expected = vec![0x7e, 0xff, 0x7d, 0x23, 0xc0, 0x21, 0x7d, 0x26, 0x7d, 0x21];
+        expected.extend_from_slice(&[0x7d, 0x20, 0x7d, 0x24]);
+        for byte in &frame[8..] {
+            match byte {
+                0x00..=0x1f | 0x7d | 0x7e => expected.extend_from_slice(&[0x7d, byte ^ 0x20]),
+                _ => expected.push(*byte),
+            }
+        }
+        expected.push(0x7e);
+
+        assert_eq!(encoder.encode(&lcp)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hdlc_stream_decode() -> Result<()> {
+        let config = HdlcConfig {
+            accm: 0,
+            fcs: Fcs::Fcs32,
+            ..Default::default()
+        };
+        let encoder = HdlcEncoder::new(config.clone());
+        let mut decoder = HdlcDecoder::new(config);
+
+        let ipv4 = PppPkt::new_ipv4(vec![0x45, 0x7e, 0x7d, 0x00, 0x11]);
+        let lcp = PppPkt::new_lcp(LcpPkt::new_echo_request(3, 0x01020304, Vec::new()));
+
+        let mut stream = encoder.encode(&ipv4)?;
+        stream.extend(encoder.encode(&lcp)?);
+
+        // Partial frames are kept until the closing flag arrives.
+        let (first, second) = stream.split_at(5);
+        decoder.handle_bytes(first);
+        assert!(decoder.poll_packet().is_none());
+
+        decoder.handle_bytes(second);
+        assert_eq!(decoder.poll_packet().unwrap()?, ipv4);
+        assert_eq!(decoder.poll_packet().unwrap()?, lcp);
+        assert!(decoder.poll_packet().is_none());
+
+        // Corrupted frames fail the FCS check, aborted frames are dropped.
+        let mut corrupted = encoder.encode(&ipv4)?;
+        corrupted[3] ^= 0x01;
+        decoder.handle_bytes(&corrupted);
+        assert!(matches!(
+            decoder.poll_packet(),
+            Some(Err(Error::InvalidFcs))
+        ));
+
+        decoder.handle_bytes(&[0x7e, 0x00, 0x21, 0x45, 0x7d, 0x7e]);
+        assert!(decoder.poll_packet().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_lcp_extension_options() -> Result<()> {
+        let configure_request = LcpPkt::new_configure_request(
+            1,
+            vec![
+                LcpOpt::AsyncControlCharacterMap(0x000a0000).into(),
+                LcpOpt::FcsAlternatives(FCS_ALTERNATIVE_32).into(),
+                LcpOpt::SelfDescribingPadding(8).into(),
+                LcpOpt::Callback(Callback {
+                    operation: CALLBACK_DIALING_STRING,
+                    message: b"123".to_vec(),
+                })
+                .into(),
+                LcpOpt::Internationalization(Internationalization {
+                    charset: 106,
+                    language_tag: "en".into(),
+                })
+                .into(),
+            ],
+        );
+
+        let mut buf = Vec::new();
+        configure_request.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x01, 0x01, 0x00, 0x1e, 0x02, 0x06, 0x00, 0x0a, 0x00, 0x00, 0x09, 0x03, 0x04, 0x0a,
+                0x03, 0x08, 0x0d, 0x06, 0x01, 0x31, 0x32, 0x33, 0x1c, 0x08, 0x00, 0x00, 0x00, 0x6a,
+                0x65, 0x6e
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_lcp_extension_options() -> Result<()> {
+        let mut configure_request = LcpPkt::default();
+
+        let buf = [
+            0x01, 0x01, 0x00, 0x1e, 0x02, 0x06, 0x00, 0x0a, 0x00, 0x00, 0x09, 0x03, 0x04, 0x0a,
+            0x03, 0x08, 0x0d, 0x06, 0x01, 0x31, 0x32, 0x33, 0x1c, 0x08, 0x00, 0x00, 0x00, 0x6a,
+            0x65, 0x6e,
+        ];
+        configure_request.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            configure_request,
+            LcpPkt::new_configure_request(
+                1,
+                vec![
+                    LcpOpt::AsyncControlCharacterMap(0x000a0000).into(),
+                    LcpOpt::FcsAlternatives(FCS_ALTERNATIVE_32).into(),
+                    LcpOpt::SelfDescribingPadding(8).into(),
+                    LcpOpt::Callback(Callback {
+                        operation: CALLBACK_DIALING_STRING,
+                        message: b"123".to_vec(),
+                    })
+                    .into(),
+                    LcpOpt::Internationalization(Internationalization {
+                        charset: 106,
+                        language_tag: "en".into(),
+                    })
+                    .into(),
+                ]
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_lcp_time_remaining() -> Result<()> {
+        let time_remaining = LcpPkt::new_time_remaining(1, 0x01020304, 300, b"bye".to_vec());
+
+        let mut buf = Vec::new();
+        time_remaining.serialize(&mut buf)?;
+
+        assert_eq!(
+            &buf,
+            &[
+                0x0d, 0x01, 0x00, 0x0f, 0x01, 0x02, 0x03, 0x04, 0x00, 0x00, 0x01, 0x2c, 0x62, 0x79,
+                0x65
+            ]
+        );
+        assert_eq!(
+            time_remaining.to_string(),
+            "LCP id=1: Time-Remaining 16909060: 300s bye"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_lcp_vendor_extension() -> Result<()> {
+        let mut vendor_extension = LcpPkt::default();
+
+        let buf = [
+            0x00, 0x02, 0x00, 0x0e, 0x01, 0x02, 0x03, 0x04, 0x00, 0x00, 0x5e, 0x07, 0x0d, 0x25,
+        ];
+        vendor_extension.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            vendor_extension,
+            LcpPkt::new_vendor_extension(2, 0x01020304, [0x00, 0x00, 0x5e], 7, vec![0x0d, 0x25])
+        );
+
+        let mut identification = LcpPkt::default();
+
+        let buf = [0x0c, 0x03, 0x00, 0x0a, 0x01, 0x02, 0x03, 0x04, 0x68, 0x69];
+        identification.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            identification,
+            LcpPkt::new_identification(3, 0x01020304, b"hi".to_vec())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ppp_multilink_headers() -> Result<()> {
+        let long = PppPkt::new_multilink(MultilinkFragment {
+            short: false,
+            begin: true,
+            end: false,
+            sequence: 0x012345,
+            data: vec![0x00, 0x21],
+        });
+
+        let mut buf = Vec::new();
+        long.serialize(&mut buf)?;
+        assert_eq!(&buf, &[0x00, 0x3d, 0x80, 0x01, 0x23, 0x45, 0x00, 0x21]);
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize(&mut buf.as_slice())?;
+        assert_eq!(pkt, long);
+
+        let short = PppPkt::new_multilink(MultilinkFragment {
+            short: true,
+            begin: false,
+            end: true,
+            sequence: 0x0abc,
+            data: vec![0x45],
+        });
+
+        let mut buf = Vec::new();
+        short.serialize(&mut buf)?;
+        assert_eq!(&buf, &[0x00, 0x3d, 0x4a, 0xbc, 0x45]);
+
+        let framing = Framing {
+            ssnhf: true,
+            ..Default::default()
+        };
+        pkt.deserialize_framed(&mut buf.as_slice(), &framing)?;
+        assert_eq!(pkt, short);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multilink_bundle_reassembly() -> Result<()> {
+        let mut tx = Bundle::new(BundleConfig::default());
+        let mut rx = Bundle::new(BundleConfig::default());
+
+        for bundle in [&mut tx, &mut rx] {
+            bundle.add_link(200);
+            bundle.add_link(200);
+        }
+
+        let ipv4 = PppPkt::new_ipv4(vec![0x45; 600]);
+        tx.send(&ipv4)?;
+
+        let mut fragments = Vec::new();
+        while let Some((link, pkt)) = tx.poll_transmit() {
+            match pkt.data {
+                PppData::Multilink(fragment) => fragments.push((link, fragment)),
+                _ => unreachable!(),
+            }
+        }
+
+        assert_eq!(
+            fragments
+                .iter()
+                .map(|(link, fragment)| (*link, fragment.data.len()))
+                .collect::<Vec<_>>(),
+            vec![(0, 194), (1, 194), (0, 194), (1, 20)]
+        );
+
+        // Fragments may arrive out of order across links.
+        for i in [1, 0, 3, 2] {
+            let (link, fragment) = &fragments[i];
+            rx.handle_fragment(*link, fragment);
+        }
+
+        assert_eq!(rx.poll_packet(), Some(ipv4));
+        assert_eq!(rx.poll_packet(), None);
+        assert_eq!(rx.stats().fragments_lost, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multilink_bundle_loss() -> Result<()> {
+        let config = BundleConfig {
+            min_fragment_len: 0,
+            ..Default::default()
+        };
+        let mut tx = Bundle::new(config.clone());
+        let mut rx = Bundle::new(config);
+
+        for bundle in [&mut tx, &mut rx] {
+            bundle.add_link(1500);
+            bundle.add_link(1500);
+        }
+
+        let first = PppPkt::new_ipv4(vec![0x45; 10]);
+        let second = PppPkt::new_ipv6(vec![0x60; 10]);
+        tx.send(&first)?;
+        tx.send(&second)?;
+
+        let mut fragments = Vec::new();
+        while let Some((link, pkt)) = tx.poll_transmit() {
+            match pkt.data {
+                PppData::Multilink(fragment) => fragments.push((link, fragment)),
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(fragments.len(), 4);
+
+        // The second fragment of the first packet is lost on link 1.
+        for i in [0, 2] {
+            let (link, fragment) = &fragments[i];
+            rx.handle_fragment(*link, fragment);
+        }
+        assert_eq!(rx.poll_packet(), None);
+
+        // Link 1 has moved past the gap, so it can't be filled anymore.
+        let (link, fragment) = &fragments[3];
+        rx.handle_fragment(*link, fragment);
+
+        assert_eq!(rx.poll_packet(), Some(second));
+        assert_eq!(rx.poll_packet(), None);
+        assert_eq!(rx.stats().fragments_lost, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multilink_bundle_idle_link() -> Result<()> {
+        let config = BundleConfig {
+            idle_link_fragments: 4,
+            ..Default::default()
+        };
+        let mut tx = Bundle::new(config.clone());
+        let mut rx = Bundle::new(config);
+
+        // Small packets aren't fragmented, they all go out on one link.
+        tx.add_link(1500);
+        rx.add_link(1500);
+        rx.add_link(1500);
+
+        let pkt = PppPkt::new_ipv4(vec![0x45; 10]);
+        for _ in 0..8 {
+            tx.send(&pkt)?;
+        }
+
+        let mut fragments = Vec::new();
+        while let Some((_, pkt)) = tx.poll_transmit() {
+            match pkt.data {
+                PppData::Multilink(fragment) => fragments.push(fragment),
+                _ => unreachable!(),
+            }
+        }
+
+        // Complete packets are delivered although link 1 never received anything.
+        rx.handle_fragment(0, &fragments[0]);
+        assert_eq!(rx.poll_packet(), Some(pkt.clone()));
+
+        // The second packet is lost, link 1 holds back the detection for a while.
+        for fragment in &fragments[2..5] {
+            rx.handle_fragment(0, fragment);
+        }
+        assert_eq!(rx.poll_packet(), None);
+
+        rx.handle_fragment(0, &fragments[5]);
+        for _ in 2..6 {
+            assert_eq!(rx.poll_packet(), Some(pkt.clone()));
+        }
+        assert_eq!(rx.poll_packet(), None);
+        assert_eq!(rx.stats().fragments_lost, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcp_negotiation_multilink() -> Result<()> {
+        use lcp::negotiation::{Negotiator, Policy};
+
+        let request = LcpConfigureRequest {
+            options: vec![
+                LcpOpt::Mrru(1524).into(),
+                LcpOpt::ShortSequenceNumberHeader.into(),
+                LcpOpt::EndpointDiscriminator(EndpointDiscriminator {
+                    class: ENDPOINT_CLASS_MAC,
+                    address: vec![0x00, 0x00, 0x5e, 0x00, 0x53, 0x01],
+                })
+                .into(),
+            ],
+        };
+
+        let mut negotiator = Negotiator::new(Policy::default());
+        assert_eq!(
+            negotiator.reply(1, &request),
+            LcpPkt::new_configure_reject(1, request.options.clone())
+        );
+
+        let policy = Policy {
+            mrru: Some(1524),
+            ssnhf: true,
+            ..Default::default()
+        };
+        let mut negotiator = Negotiator::new(policy);
+        assert_eq!(
+            negotiator.reply(2, &request),
+            LcpPkt::new_configure_ack(2, request.options.clone())
+        );
+        assert_eq!(negotiator.peer().mrru, Some(1524));
+        assert!(negotiator.transmit_framing(false).ssnhf);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_lqr() -> Result<()> {
+        let expected = [
+            0xc0, 0x25, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03,
+            0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00,
+            0x00, 0x07, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x0a,
+            0x00, 0x00, 0x00, 0x0b, 0x00, 0x00, 0x00, 0x0c,
+        ];
+
+        let lqr = LqrPkt {
+            magic_number: 1,
+            last_out_lqrs: 2,
+            last_out_packets: 3,
+            last_out_octets: 4,
+            peer_in_lqrs: 5,
+            peer_in_packets: 6,
+            peer_in_discards: 7,
+            peer_in_errors: 8,
+            peer_in_octets: 9,
+            peer_out_lqrs: 10,
+            peer_out_packets: 11,
+            peer_out_octets: 12,
+        };
+
+        let mut buf = Vec::new();
+        PppPkt::new_lqr(lqr.clone()).serialize(&mut buf)?;
+        assert_eq!(&buf, &expected);
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize(&mut expected.as_ref())?;
+        assert_eq!(pkt, PppPkt::new_lqr(lqr));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lqr_monitor_loss() {
+        let start = std::time::Instant::now();
+        let interval = std::time::Duration::from_secs(10);
+
+        let config = LqrConfig::with_reporting_period(1000);
+        assert_eq!(config.interval, Some(interval));
+        let mut a = LqrMonitor::new(config.clone(), 1);
+        let mut b = LqrMonitor::new(config, 2);
+        let mut ca = LinkCounters::default();
+        let mut cb = LinkCounters::default();
+
+        a.start(start, &ca);
+        let pa1 = a.poll_transmit().unwrap();
+        ca.out_packets += 1;
+        b.start(start, &cb);
+        let pb1 = b.poll_transmit().unwrap();
+        cb.out_packets += 1;
+
+        cb.in_packets += 1;
+        b.handle_packet(&pa1, start, &cb);
+        ca.in_packets += 1;
+        a.handle_packet(&pb1, start, &ca);
+        assert_eq!(a.poll_transmit(), None);
+        assert_eq!(a.poll_timeout(), Some(start + interval));
+
+        // 10 out of 100 data packets from a to b are lost.
+        ca.out_packets += 100;
+        cb.in_packets += 90;
+
+        a.handle_timeout(start + interval, &ca);
+        let pa2 = a.poll_transmit().unwrap();
+        assert_eq!(pa2.peer_out_packets, 102);
+        ca.out_packets += 1;
+        cb.in_packets += 1;
+        b.handle_packet(&pa2, start + interval, &cb);
+
+        b.handle_timeout(start + interval, &cb);
+        let pb2 = b.poll_transmit().unwrap();
+        assert_eq!(pb2.last_out_packets, 102);
+        assert_eq!(pb2.peer_in_packets, 92);
+        ca.in_packets += 1;
+        a.handle_packet(&pb2, start + interval, &ca);
+
+        assert_eq!(
+            a.loss(),
+            LqrLoss {
+                inbound: 0.0,
+                outbound: 10.0 / 102.0,
+            }
+        );
+        assert_eq!(
+            b.loss(),
+            LqrLoss {
+                inbound: 10.0 / 101.0,
+                outbound: 0.0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_serialize_ccp_configure_request() -> Result<()> {
+        let expected = [
+            0x80, 0xfd, 0x01, 0x01, 0x00, 0x0b, 0x15, 0x03, 0x2f, 0x1a, 0x04, 0x78, 0x00,
+        ];
+
+        let pkt = PppPkt::new_ccp(CcpPkt::new_configure_request(
+            1,
+            vec![
+                CcpOpt::BsdCompress(BsdCompress::default()).into(),
+                CcpOpt::Deflate(Deflate::default()).into(),
+            ],
+        ));
+
+        let mut buf = Vec::new();
+        pkt.serialize(&mut buf)?;
+        assert_eq!(&buf, &expected);
+
+        let mut deserialized = PppPkt::default();
+        deserialized.deserialize(&mut expected.as_ref())?;
+        assert_eq!(deserialized, pkt);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_ccp_reset_request() -> Result<()> {
+        let buf = [0x80, 0xfd, 0x0e, 0x02, 0x00, 0x04];
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize(&mut buf.as_ref())?;
+
+        assert_eq!(
+            pkt,
+            PppPkt::new_ccp(CcpPkt::new_reset_request(2, Vec::new()))
+        );
+        Ok(())
+    }
+
+    fn deflate_compression() -> (ccp::compression::Compression, ccp::compression::Compression) {
+        use ccp::compression::{Compression, CompressionConfig};
+        use ccp::deflate::{DeflateCompressor, DeflateDecompressor};
+
+        let mut tx = Compression::new(CompressionConfig::default());
+        tx.set_compressor(Some(Box::new(DeflateCompressor::new())));
+        let mut rx = Compression::new(CompressionConfig::default());
+        rx.set_decompressor(Some(Box::new(DeflateDecompressor::new())));
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_ccp_deflate_round_trip() -> Result<()> {
+        let (mut tx, mut rx) = deflate_compression();
+        let now = std::time::Instant::now();
+
+        let mut noise = 0x1234_5678u32;
+        let random: Vec<u8> = (0..200)
+            .map(|_| {
+                noise ^= noise << 13;
+                noise ^= noise >> 17;
+                noise ^= noise << 5;
+                noise as u8
+            })
+            .collect();
+
+        let pkts = [
+            PppPkt::new_ipv4(b"low bandwidth backup link ".repeat(20)),
+            PppPkt::new_ipv4(random),
+            PppPkt::new_ipv6(b"low bandwidth backup link ".repeat(20)),
+            PppPkt::new_lcp(LcpPkt::new_echo_request(1, 0, Vec::new())),
+        ];
+
+        for pkt in pkts {
+            let sent = tx.compress(&pkt)?;
+            match pkt.protocol() {
+                IPV6 => assert!(sent.len() < 40),
+                _ if pkt.ipv4().is_some_and(|datagram| datagram[0] == b'l') => {
+                    assert!(matches!(sent.data, PppData::Compressed(_)));
+                }
+                _ => assert_eq!(sent, pkt),
+            }
+
+            assert_eq!(rx.handle_packet(sent, now), Some(pkt));
+        }
+
+        assert_eq!(tx.stats().packets_compressed, 2);
+        assert_eq!(rx.stats().packets_decompressed, 2);
+        assert_eq!(rx.poll_transmit(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ccp_deflate_reset() -> Result<()> {
+        let (mut tx, mut rx) = deflate_compression();
+        let now = std::time::Instant::now();
+
+        let pkt = PppPkt::new_ipv4(b"reset me ".repeat(30));
+
+        // The first datagram is lost, the second is out of sequence.
+        tx.compress(&pkt)?;
+        let sent = tx.compress(&pkt)?;
+        assert_eq!(rx.handle_packet(sent, now), None);
+
+        let reset_request = rx.poll_transmit().unwrap();
+        assert!(matches!(reset_request.data, CcpData::ResetRequest(_)));
+        assert_eq!(
+            rx.poll_timeout(),
+            Some(now + std::time::Duration::from_secs(3))
+        );
+
+        // Datagrams are discarded until the Reset-Ack arrives.
+        let sent = tx.compress(&pkt)?;
+        assert_eq!(rx.handle_packet(sent, now), None);
+
+        assert_eq!(
+            tx.handle_packet(PppPkt::new_ccp(reset_request.clone()), now),
+            None
+        );
+        let reset_ack = tx.poll_transmit().unwrap();
+        assert_eq!(
+            reset_ack,
+            CcpPkt::new_reset_ack(reset_request.identifier, Vec::new())
+        );
+
+        assert_eq!(rx.handle_packet(PppPkt::new_ccp(reset_ack), now), None);
+        assert_eq!(rx.poll_timeout(), None);
+
+        let sent = tx.compress(&pkt)?;
+        assert_eq!(rx.handle_packet(sent, now), Some(pkt));
+        assert_eq!(rx.stats().packets_discarded, 2);
+        assert_eq!(rx.stats().resets_sent, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_ccp_mppe() -> Result<()> {
+        let expected = [0x12, 0x06, 0x01, 0x00, 0x00, 0x40];
+
+        let option: CcpOption = CcpOpt::Mppe(Mppe {
+            stateless: true,
+            bits_128: true,
+            ..Default::default()
+        })
+        .into();
+
+        let mut buf = Vec::new();
+        option.serialize(&mut buf)?;
+        assert_eq!(&buf, &expected);
+
+        let mut deserialized = CcpOption::from(CcpOpt::Deflate(Deflate::default()));
+        deserialized.deserialize(&mut expected.as_ref())?;
+        assert_eq!(deserialized, option);
+        Ok(())
+    }
+
+    const RFC3079_NT_RESPONSE: [u8; 24] = [
+        0x82, 0x30, 0x9e, 0xcd, 0x8d, 0x70, 0x8b, 0x5e, 0xa0, 0x8f, 0xaa, 0x39, 0x81, 0xcd, 0x83,
+        0x54, 0x42, 0x33, 0x11, 0x4a, 0x3d, 0x85, 0xd6, 0xdf,
+    ];
+
+    #[test]
+    fn test_mppe_keys_rfc3079() {
+        use ccp::mppe::{nt_password_hash, MppeKeys};
+
+        assert_eq!(
+            nt_password_hash("clientPass"),
+            [
+                0x44, 0xeb, 0xba, 0x8d, 0x53, 0x12, 0xb8, 0xd6, 0x11, 0x47, 0x44, 0x11, 0xf5, 0x69,
+                0x89, 0xae
+            ]
+        );
+
+        let server = MppeKeys::from_password("clientPass", &RFC3079_NT_RESPONSE, true);
+        assert_eq!(
+            server.send,
+            [
+                0x8b, 0x7c, 0xdc, 0x14, 0x9b, 0x99, 0x3a, 0x1b, 0xa1, 0x18, 0xcb, 0x15, 0x3f, 0x56,
+                0xdc, 0xcb
+            ]
+        );
+
+        let client = MppeKeys::from_password("clientPass", &RFC3079_NT_RESPONSE, false);
+        assert_eq!(client.receive, server.send);
+        assert_eq!(client.send, server.receive);
+    }
+
+    #[test]
+    fn test_mppe_encrypt_rfc3079() -> Result<()> {
+        use ccp::compression::{Compressor, Decompressor};
+        use ccp::mppe::{MppeCompressor, MppeDecompressor, MppeKeys, MppeStrength};
+
+        let server = MppeKeys::from_password("clientPass", &RFC3079_NT_RESPONSE, true);
+        let client = MppeKeys::from_password("clientPass", &RFC3079_NT_RESPONSE, false);
+
+        let mut tx = MppeCompressor::new(&server.send, MppeStrength::Bits128, false);
+        let mut rx = MppeDecompressor::new(&client.receive, MppeStrength::Bits128, false);
+
+        let pkt = PppPkt::new_ipv4(b"test message".to_vec());
+        let encrypted = tx.compress(&pkt)?;
+        assert_eq!(
+            encrypted,
+            PppPkt::new_compressed(vec![
+                0x10, 0x00, 0xf5, 0xc0, 0x84, 0x06, 0x8c, 0x71, 0xc1, 0x7c, 0x64, 0xe9, 0x4e, 0xba,
+                0xf8, 0xa7
+            ])
+        );
+
+        match encrypted.data {
+            PppData::Compressed(data) => assert_eq!(rx.decompress(&data)?, pkt),
+            _ => unreachable!(),
+        }
+        Ok(())
+    }
+
+    fn mppe_compression(
+        strength: ccp::mppe::MppeStrength,
+        stateless: bool,
+    ) -> (ccp::compression::Compression, ccp::compression::Compression) {
+        use ccp::compression::{Compression, CompressionConfig};
+        use ccp::mppe::{MppeCompressor, MppeDecompressor, MppeKeys};
+
+        let server = MppeKeys::from_password("clientPass", &RFC3079_NT_RESPONSE, true);
+        let client = MppeKeys::from_password("clientPass", &RFC3079_NT_RESPONSE, false);
+
+        let mut tx = Compression::new(CompressionConfig::default());
+        tx.set_compressor(Some(Box::new(MppeCompressor::new(
+            &server.send,
+            strength,
+            stateless,
+        ))));
+        let mut rx = Compression::new(CompressionConfig::default());
+        rx.set_decompressor(Some(Box::new(MppeDecompressor::new(
+            &client.receive,
+            strength,
+            stateless,
+        ))));
+
+        (tx, rx)
+    }
+
+    #[test]
+    fn test_mppe_stateless_loss() -> Result<()> {
+        let (mut tx, mut rx) = mppe_compression(ccp::mppe::MppeStrength::Bits40, true);
+        let now = std::time::Instant::now();
+
+        for i in 0..600u16 {
+            let pkt = PppPkt::new_ipv6(i.to_be_bytes().repeat(10));
+            let sent = tx.compress(&pkt)?;
+
+            // Every third packet is lost.
+            if i % 3 != 1 {
+                assert_eq!(rx.handle_packet(sent, now), Some(pkt));
+            }
+        }
+
+        assert_eq!(rx.poll_transmit(), None);
+        assert_eq!(rx.stats().packets_discarded, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mppe_stateful_resync() -> Result<()> {
+        let (mut tx, mut rx) = mppe_compression(ccp::mppe::MppeStrength::Bits56, false);
+        let now = std::time::Instant::now();
+
+        // Cross a flag packet so that the receiver has to catch up on a rekey.
+        for i in 0..300u16 {
+            let pkt = PppPkt::new_ipv4(i.to_be_bytes().repeat(10));
+            let sent = tx.compress(&pkt)?;
+
+            if (200..=260).contains(&i) {
+                continue;
+            }
+
+            if i != 261 {
+                assert_eq!(rx.handle_packet(sent, now), Some(pkt));
+            } else {
+                // The gap is detected, the peer is asked to flush.
+                assert_eq!(rx.handle_packet(sent, now), None);
+
+                let reset_request = rx.poll_transmit().unwrap();
+                assert!(matches!(reset_request.data, CcpData::ResetRequest(_)));
+                assert_eq!(tx.handle_packet(PppPkt::new_ccp(reset_request), now), None);
+            }
+        }
+
+        // Unencrypted datagrams are refused, control packets aren't.
+        assert_eq!(rx.handle_packet(PppPkt::new_ipv4(vec![0x45]), now), None);
+        let lcp = PppPkt::new_lcp(LcpPkt::new_echo_request(1, 0, Vec::new()));
+        assert_eq!(tx.compress(&lcp)?, lcp);
+        assert_eq!(rx.handle_packet(lcp.clone(), now), Some(lcp));
+
+        // Packet 261 and the unencrypted datagram were discarded.
+        assert_eq!(rx.stats().packets_discarded, 2);
+        assert_eq!(rx.poll_timeout(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_ecp_configure_request() -> Result<()> {
+        let expected = [
+            0x80, 0x53, 0x01, 0x07, 0x00, 0x18, 0x03, 0x0a, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+            0x07, 0x08, 0x02, 0x0a, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10,
+        ];
+
+        let pkt = PppPkt::new_ecp(EcpPkt::new_configure_request(
+            7,
+            vec![
+                EcpOpt::DeseBis([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]).into(),
+                EcpOpt::TripleDese([0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0x10]).into(),
+            ],
+        ));
+
+        let mut buf = Vec::new();
+        pkt.serialize(&mut buf)?;
+        assert_eq!(&buf, &expected);
+
+        let mut deserialized = PppPkt::default();
+        deserialized.deserialize(&mut expected.as_ref())?;
+        assert_eq!(deserialized, pkt);
+
+        // A single DESE-bis option as sent by a peer.
+        let peer = [
+            0x80, 0x53, 0x01, 0x01, 0x00, 0x0e, 0x03, 0x0a, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+            0x07, 0x08,
+        ];
+
+        let mut deserialized = PppPkt::default();
+        deserialized.deserialize(&mut peer.as_ref())?;
+        assert_eq!(
+            deserialized,
+            PppPkt::new_ecp(EcpPkt::new_configure_request(
+                1,
+                vec![EcpOpt::DeseBis([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]).into()],
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize_ecp_reset_ack_and_encrypted() -> Result<()> {
+        let reset_ack = [0x80, 0x53, 0x0f, 0x03, 0x00, 0x04];
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize(&mut reset_ack.as_ref())?;
+        assert_eq!(pkt, PppPkt::new_ecp(EcpPkt::new_reset_ack(3, Vec::new())));
+
+        let encrypted = [0x00, 0x53, 0x00, 0x01, 0xde, 0xad, 0xbe, 0xef];
+
+        let mut pkt = PppPkt::default();
+        pkt.deserialize(&mut encrypted.as_ref())?;
+        assert_eq!(
+            pkt,
+            PppPkt::new_encrypted(vec![0x00, 0x01, 0xde, 0xad, 0xbe, 0xef])
+        );
+        Ok(())
+    }
+
+    const TEST_ECP_NONCE: [u8; 8] = [0x13, 0x37, 0x13, 0x37, 0x13, 0x37, 0x13, 0x37];
+
+    struct TestEcp;
+
+    impl ControlProtocol for TestEcp {
+        type Packet = EcpPkt;
+
+        fn request_options(&mut self) -> Vec<EcpOption> {
+            vec![EcpOpt::TripleDese(TEST_ECP_NONCE).into()]
+        }
+
+        fn check_request(&mut self, options: &[EcpOption]) -> FsmVerdict<EcpOption> {
+            let rejected: Vec<EcpOption> = options
+                .iter()
+                .filter(|option| !matches!(option.value, EcpOpt::TripleDese(_)))
+                .cloned()
+                .collect();
+
+            if rejected.is_empty() {
+                FsmVerdict::Ack
+            } else {
+                FsmVerdict::Reject(rejected)
+            }
+        }
+    }
+
+    #[test]
+    fn test_fsm_ecp() {
+        let now = std::time::Instant::now();
+
+        let mut fsm = Fsm::new(TestEcp, FsmConfig::default());
+        fsm.up(now);
+        fsm.open(now);
+        assert_eq!(
+            fsm.poll_transmit(),
+            Some(EcpPkt::new_configure_request(
+                0,
+                vec![EcpOpt::TripleDese(TEST_ECP_NONCE).into()]
+            ))
+        );
+
+        fsm.handle_packet(
+            &EcpPkt::new_configure_request(0x41, vec![EcpOpt::DeseBis([0xff; 8]).into()]),
+            now,
+        );
+        assert_eq!(
+            fsm.poll_transmit(),
+            Some(EcpPkt::new_configure_reject(
+                0x41,
+                vec![EcpOpt::DeseBis([0xff; 8]).into()]
+            ))
+        );
+
+        // Resets are left to the encryption layer.
+        fsm.handle_packet(&EcpPkt::new_reset_request(0x42, Vec::new()), now);
+        assert_eq!(fsm.poll_transmit(), None);
+    }
 }
@@ -0,0 +1,260 @@
+use crate::{Deserialize, Serialize};
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use ppproperly_macros::{Deserialize, Serialize};
+
+/// Link-Quality-Report as described in RFC 1989.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LqrPkt {
+    pub magic_number: u32,
+    pub last_out_lqrs: u32,
+    pub last_out_packets: u32,
+    pub last_out_octets: u32,
+    pub peer_in_lqrs: u32,
+    pub peer_in_packets: u32,
+    pub peer_in_discards: u32,
+    pub peer_in_errors: u32,
+    pub peer_in_octets: u32,
+    pub peer_out_lqrs: u32,
+    pub peer_out_packets: u32,
+    pub peer_out_octets: u32,
+}
+
+impl LqrPkt {
+    pub fn len(&self) -> u16 {
+        48
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl fmt::Display for LqrPkt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "LQR {}: out lqrs={} pkts={} octets={}, peer in lqrs={} pkts={} discards={} errors={} octets={}, peer out lqrs={} pkts={} octets={}",
+            self.magic_number,
+            self.last_out_lqrs,
+            self.last_out_packets,
+            self.last_out_octets,
+            self.peer_in_lqrs,
+            self.peer_in_packets,
+            self.peer_in_discards,
+            self.peer_in_errors,
+            self.peer_in_octets,
+            self.peer_out_lqrs,
+            self.peer_out_packets,
+            self.peer_out_octets
+        )
+    }
+}
+
+/// Interface counters of a link as maintained by the caller.
+/// All of them wrap around.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LinkCounters {
+    /// Packets sent, including LQRs.
+    pub out_packets: u32,
+    pub out_octets: u32,
+    /// Packets received without errors, including LQRs.
+    pub in_packets: u32,
+    /// Packets received without errors but discarded.
+    pub in_discards: u32,
+    /// Packets received with errors, e.g. a bad FCS.
+    pub in_errors: u32,
+    pub in_octets: u32,
+}
+
+/// Settings of an [`LqrMonitor`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LqrConfig {
+    /// Time between two LQRs, the Reporting-Period the peer negotiated.
+    /// `None` only sends LQRs in response to the ones of the peer.
+    pub interval: Option<Duration>,
+    /// Number of received LQRs the loss is computed over.
+    pub window: usize,
+}
+
+impl Default for LqrConfig {
+    fn default() -> Self {
+        Self {
+            interval: Some(Duration::from_secs(10)),
+            window: 10,
+        }
+    }
+}
+
+impl LqrConfig {
+    /// Creates a config from the Reporting-Period of the peer's
+    /// [`crate::QualityProtocol::LinkQualityReport`] option
+    /// in hundredths of a second, 0 meaning no timer.
+    pub fn with_reporting_period(reporting_period: u32) -> Self {
+        Self {
+            interval: (reporting_period > 0)
+                .then(|| Duration::from_millis(u64::from(reporting_period) * 10)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Fraction of packets lost in each direction, 0.0 to 1.0.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LqrLoss {
+    pub inbound: f64,
+    pub outbound: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Sample {
+    out_sent: u32,
+    out_lost: u32,
+    in_sent: u32,
+    in_lost: u32,
+}
+
+/// Sans-IO Link Quality Monitoring as described in RFC 1989.
+///
+/// Feed received LQRs into [`LqrMonitor::handle_packet`] and call
+/// [`LqrMonitor::handle_timeout`] once [`LqrMonitor::poll_timeout`] has passed,
+/// both along with the current link counters. LQRs to send are returned
+/// by [`LqrMonitor::poll_transmit`], the loss over the most recent
+/// reports by [`LqrMonitor::loss`].
+#[derive(Clone, Debug)]
+pub struct LqrMonitor {
+    config: LqrConfig,
+    magic_number: u32,
+    out_lqrs: u32,
+    in_lqrs: u32,
+    /// Most recent LQR of the peer along with our counters when it arrived.
+    last: Option<(LqrPkt, LinkCounters)>,
+    saved_in_lqrs: u32,
+    saved: LinkCounters,
+    samples: VecDeque<Sample>,
+    deadline: Option<Instant>,
+    transmit: VecDeque<LqrPkt>,
+}
+
+impl LqrMonitor {
+    /// Creates a monitor using our negotiated magic number,
+    /// 0 if none was negotiated.
+    pub fn new(config: LqrConfig, magic_number: u32) -> Self {
+        Self {
+            config,
+            magic_number,
+            out_lqrs: 0,
+            in_lqrs: 0,
+            last: None,
+            saved_in_lqrs: 0,
+            saved: LinkCounters::default(),
+            samples: VecDeque::new(),
+            deadline: None,
+            transmit: VecDeque::new(),
+        }
+    }
+
+    /// Starts sending LQRs, e.g. once LCP is opened.
+    pub fn start(&mut self, now: Instant, counters: &LinkCounters) {
+        match self.config.interval {
+            Some(_) => self.send_lqr(now, counters),
+            None => self.deadline = None,
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Handles an LQR of the peer. `counters` have to include the LQR itself.
+    pub fn handle_packet(&mut self, pkt: &LqrPkt, now: Instant, counters: &LinkCounters) {
+        self.in_lqrs = self.in_lqrs.wrapping_add(1);
+        self.saved_in_lqrs = self.in_lqrs;
+        self.saved = *counters;
+
+        if let Some((last, last_counters)) = &self.last {
+            let out_sent = pkt.last_out_packets.wrapping_sub(last.last_out_packets);
+            let out_received = pkt.peer_in_packets.wrapping_sub(last.peer_in_packets);
+            let in_sent = pkt.peer_out_packets.wrapping_sub(last.peer_out_packets);
+            let in_received = counters.in_packets.wrapping_sub(last_counters.in_packets);
+
+            self.samples.push_back(Sample {
+                out_sent,
+                out_lost: out_sent.saturating_sub(out_received),
+                in_sent,
+                in_lost: in_sent.saturating_sub(in_received),
+            });
+
+            while self.samples.len() > self.config.window {
+                self.samples.pop_front();
+            }
+        }
+
+        self.last = Some((pkt.clone(), *counters));
+
+        if self.config.interval.is_none() {
+            self.send_lqr(now, counters);
+        }
+    }
+
+    pub fn handle_timeout(&mut self, now: Instant, counters: &LinkCounters) {
+        match self.deadline {
+            Some(deadline) if deadline <= now => self.send_lqr(now, counters),
+            _ => {}
+        }
+    }
+
+    /// Returns the point in time at which [`LqrMonitor::handle_timeout`] needs to be called.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<LqrPkt> {
+        self.transmit.pop_front()
+    }
+
+    /// Returns the packet loss over the configured window of received LQRs.
+    pub fn loss(&self) -> LqrLoss {
+        let ratio = |lost: u64, sent: u64| {
+            if sent == 0 {
+                0.0
+            } else {
+                lost as f64 / sent as f64
+            }
+        };
+
+        let sum = |f: fn(&Sample) -> u32| self.samples.iter().map(|s| u64::from(f(s))).sum();
+
+        LqrLoss {
+            inbound: ratio(sum(|s| s.in_lost), sum(|s| s.in_sent)),
+            outbound: ratio(sum(|s| s.out_lost), sum(|s| s.out_sent)),
+        }
+    }
+
+    fn send_lqr(&mut self, now: Instant, counters: &LinkCounters) {
+        self.out_lqrs = self.out_lqrs.wrapping_add(1);
+
+        let last = self.last.as_ref().map(|(last, _)| last);
+
+        self.transmit.push_back(LqrPkt {
+            magic_number: self.magic_number,
+            last_out_lqrs: last.map(|last| last.peer_out_lqrs).unwrap_or_default(),
+            last_out_packets: last.map(|last| last.peer_out_packets).unwrap_or_default(),
+            last_out_octets: last.map(|last| last.peer_out_octets).unwrap_or_default(),
+            peer_in_lqrs: self.saved_in_lqrs,
+            peer_in_packets: self.saved.in_packets,
+            peer_in_discards: self.saved.in_discards,
+            peer_in_errors: self.saved.in_errors,
+            peer_in_octets: self.saved.in_octets,
+            peer_out_lqrs: self.out_lqrs,
+            // The LQR itself is part of the packet count.
+            peer_out_packets: counters.out_packets.wrapping_add(1),
+            peer_out_octets: counters.out_octets,
+        });
+
+        self.deadline = self.config.interval.map(|interval| now + interval);
+    }
+}
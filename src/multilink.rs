@@ -0,0 +1,419 @@
+use crate::{Deserialize, Error, Framing, PppPkt, Result, Serialize, DEFAULT_MRU};
+
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{Read, Write};
+
+pub const MULTILINK_SHORT_SEQUENCE_MASK: u32 = 0x0fff;
+pub const MULTILINK_LONG_SEQUENCE_MASK: u32 = 0x00ff_ffff;
+
+const MULTILINK_BEGIN: u32 = 0x8000_0000;
+const MULTILINK_END: u32 = 0x4000_0000;
+
+/// Multilink fragment as described in RFC 1990.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MultilinkFragment {
+    /// Whether the 12-bit Short Sequence Number Header Format is used.
+    pub short: bool,
+    /// Whether this is the first fragment of a packet.
+    pub begin: bool,
+    /// Whether this is the last fragment of a packet.
+    pub end: bool,
+    pub sequence: u32,
+    /// Part of the PPP packet, the first fragment starts with its protocol field.
+    pub data: Vec<u8>,
+}
+
+impl Serialize for MultilinkFragment {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut header = self.sequence & self.sequence_mask();
+        if self.begin {
+            header |= MULTILINK_BEGIN;
+        }
+        if self.end {
+            header |= MULTILINK_END;
+        }
+
+        if self.short {
+            ((header >> 16) as u16 | header as u16).serialize(w)?;
+        } else {
+            header.serialize(w)?;
+        }
+
+        self.data.serialize(w)
+    }
+}
+
+impl Deserialize for MultilinkFragment {
+    /// Deserializes a fragment using the default long sequence number header.
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut header = u32::default();
+        header.deserialize(r)?;
+
+        self.set_header(header, false);
+        self.data.clear();
+        self.data.deserialize(r)
+    }
+}
+
+impl MultilinkFragment {
+    /// Deserializes a fragment using the short sequence number header.
+    pub fn deserialize_short<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut header = u16::default();
+        header.deserialize(r)?;
+
+        let header = u32::from(header);
+        self.set_header(
+            (header & 0xc000) << 16 | header & MULTILINK_SHORT_SEQUENCE_MASK,
+            true,
+        );
+        self.data.clear();
+        self.data.deserialize(r)
+    }
+
+    pub fn sequence_mask(&self) -> u32 {
+        if self.short {
+            MULTILINK_SHORT_SEQUENCE_MASK
+        } else {
+            MULTILINK_LONG_SEQUENCE_MASK
+        }
+    }
+
+    pub fn len(&self) -> u16 {
+        let header = if self.short { 2 } else { 4 };
+        (header + self.data.len()).try_into().unwrap()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn set_header(&mut self, header: u32, short: bool) {
+        self.short = short;
+        self.begin = header & MULTILINK_BEGIN != 0;
+        self.end = header & MULTILINK_END != 0;
+        self.sequence = header & self.sequence_mask();
+    }
+}
+
+/// Settings of a [`Bundle`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BundleConfig {
+    /// Whether to send short sequence number headers, i.e. whether the peer
+    /// requested the Short Sequence Number Header Format.
+    pub short_sequence: bool,
+    /// Largest reassembled packet accepted, the MRRU we negotiated.
+    pub mrru: u16,
+    /// Packets shorter than this aren't split across member links.
+    pub min_fragment_len: usize,
+    /// Maximum number of fragments waiting for reassembly.
+    /// Missing fragments are considered lost when this is exceeded.
+    pub max_fragments: usize,
+    /// Number of fragments the other links may receive while a link stays
+    /// silent before that link is no longer waited for to detect losses.
+    pub idle_link_fragments: u64,
+}
+
+impl Default for BundleConfig {
+    fn default() -> Self {
+        Self {
+            short_sequence: false,
+            mrru: DEFAULT_MRU,
+            min_fragment_len: 256,
+            max_fragments: 256,
+            idle_link_fragments: 32,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct BundleStats {
+    pub fragments_sent: u64,
+    pub fragments_received: u64,
+    pub packets_reassembled: u64,
+    /// Number of fragments that never arrived or had to be discarded.
+    pub fragments_lost: u64,
+}
+
+#[derive(Clone, Debug)]
+struct MemberLink {
+    mtu: u16,
+    last_sequence: Option<u64>,
+    /// Value of [`BundleStats::fragments_received`] when this link
+    /// last received a fragment or was added.
+    received_at: u64,
+}
+
+/// Sans-IO multilink bundle as described in RFC 1990.
+///
+/// Outgoing packets passed to [`Bundle::send`] are split across the member
+/// links and returned by [`Bundle::poll_transmit`] along with the link
+/// to send them on. Fragments received on any member link are fed into
+/// [`Bundle::handle_fragment`], reassembled packets are returned by
+/// [`Bundle::poll_packet`].
+///
+/// A missing fragment is considered lost once every member link has
+/// delivered a later one, fragments of the affected packet are discarded.
+/// Links that stay silent for [`BundleConfig::idle_link_fragments`]
+/// fragments received on the others are left out of this.
+/// Sequence numbers start at 0 in both directions.
+#[derive(Clone, Debug)]
+pub struct Bundle {
+    config: BundleConfig,
+    links: Vec<Option<MemberLink>>,
+    next_link: usize,
+    next_sequence: u32,
+    expected: u64,
+    fragments: BTreeMap<u64, MultilinkFragment>,
+    stats: BundleStats,
+    transmit: VecDeque<(usize, PppPkt)>,
+    packets: VecDeque<PppPkt>,
+}
+
+impl Bundle {
+    pub fn new(config: BundleConfig) -> Self {
+        Self {
+            config,
+            links: Vec::new(),
+            next_link: 0,
+            next_sequence: 0,
+            expected: 0,
+            fragments: BTreeMap::new(),
+            stats: BundleStats::default(),
+            transmit: VecDeque::new(),
+            packets: VecDeque::new(),
+        }
+    }
+
+    /// Adds a member link with the given MTU (the MRU of the peer),
+    /// returning its index.
+    pub fn add_link(&mut self, mtu: u16) -> usize {
+        self.links.push(Some(MemberLink {
+            mtu,
+            last_sequence: None,
+            received_at: self.stats.fragments_received,
+        }));
+
+        self.links.len() - 1
+    }
+
+    /// Removes a member link, e.g. after its LCP went down.
+    pub fn remove_link(&mut self, link: usize) {
+        if let Some(slot) = self.links.get_mut(link) {
+            *slot = None;
+        }
+
+        self.reassemble();
+    }
+
+    /// Splits a packet into fragments and distributes them across the member links.
+    pub fn send(&mut self, pkt: &PppPkt) -> Result<()> {
+        let header_len = if self.config.short_sequence { 2 } else { 4 };
+
+        // Start with the link after the one used last.
+        let mut links: Vec<(usize, usize)> = self
+            .links
+            .iter()
+            .enumerate()
+            .filter_map(|(i, link)| {
+                let link = link.as_ref()?;
+                let capacity = usize::from(link.mtu).saturating_sub(2 + header_len);
+
+                (capacity > 0).then_some((i, capacity))
+            })
+            .collect();
+        if links.is_empty() {
+            return Err(Error::EmptyBundle);
+        }
+
+        let first = links
+            .iter()
+            .position(|(i, _)| *i >= self.next_link)
+            .unwrap_or(0);
+        links.rotate_left(first);
+
+        let mut payload = Vec::new();
+        pkt.serialize(&mut payload)?;
+
+        let chunk_len = payload
+            .len()
+            .div_ceil(links.len())
+            .max(self.config.min_fragment_len);
+
+        let mut offset = 0;
+        for (link, capacity) in links.iter().cycle() {
+            let end = payload.len().min(offset + chunk_len.min(*capacity));
+
+            let fragment = MultilinkFragment {
+                short: self.config.short_sequence,
+                begin: offset == 0,
+                end: end == payload.len(),
+                sequence: self.next_sequence,
+                data: payload[offset..end].to_vec(),
+            };
+
+            self.next_sequence = self.next_sequence.wrapping_add(1) & fragment.sequence_mask();
+            self.next_link = link + 1;
+            self.stats.fragments_sent += 1;
+            self.transmit
+                .push_back((*link, PppPkt::new_multilink(fragment)));
+
+            offset = end;
+            if offset == payload.len() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handles a fragment received on the given member link.
+    pub fn handle_fragment(&mut self, link: usize, fragment: &MultilinkFragment) {
+        let mask = fragment.sequence_mask();
+
+        // Sequence numbers more than half the space behind are late duplicates.
+        let diff = fragment.sequence.wrapping_sub(self.expected as u32) & mask;
+        if diff > mask / 2 {
+            self.stats.fragments_lost += 1;
+            return;
+        }
+
+        let sequence = self.expected + u64::from(diff);
+
+        let received = self.stats.fragments_received + 1;
+
+        match self.links.get_mut(link) {
+            Some(Some(link)) => {
+                link.last_sequence = Some(sequence);
+                link.received_at = received;
+            }
+            _ => return,
+        }
+
+        self.stats.fragments_received = received;
+        self.fragments.insert(sequence, fragment.clone());
+
+        self.reassemble();
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<(usize, PppPkt)> {
+        self.transmit.pop_front()
+    }
+
+    pub fn poll_packet(&mut self) -> Option<PppPkt> {
+        self.packets.pop_front()
+    }
+
+    pub fn stats(&self) -> &BundleStats {
+        &self.stats
+    }
+
+    /// Returns the lowest of the most recent sequence numbers of all active
+    /// member links. Anything missing below it is never going to arrive.
+    /// Unknown as long as an active link hasn't received a fragment yet.
+    fn min_link_sequence(&self) -> Option<u64> {
+        self.links
+            .iter()
+            .flatten()
+            .filter(|link| {
+                self.stats.fragments_received - link.received_at <= self.config.idle_link_fragments
+            })
+            .map(|link| link.last_sequence)
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min()
+    }
+
+    fn reassemble(&mut self) {
+        loop {
+            let lost_below = if self.fragments.len() > self.config.max_fragments {
+                u64::MAX
+            } else {
+                // Complete packets can still be delivered in order.
+                self.min_link_sequence().unwrap_or(0)
+            };
+
+            let begin = match self.fragments.get(&self.expected) {
+                Some(fragment) => fragment.begin,
+                None => {
+                    // Skip ahead to the next fragment we have, if the gap is lost.
+                    let next = match self.fragments.keys().next() {
+                        Some(next) => *next,
+                        None => return,
+                    };
+                    if self.expected >= lost_below {
+                        return;
+                    }
+
+                    let next = next.min(lost_below);
+                    self.stats.fragments_lost += next - self.expected;
+                    self.expected = next;
+                    continue;
+                }
+            };
+
+            if !begin {
+                // The start of this packet has been lost.
+                self.fragments.remove(&self.expected);
+                self.stats.fragments_lost += 1;
+                self.expected += 1;
+                continue;
+            }
+
+            let mut sequence = self.expected;
+            let complete = loop {
+                match self.fragments.get(&sequence) {
+                    Some(fragment) if fragment.end => break true,
+                    Some(_) => sequence += 1,
+                    None => break false,
+                }
+            };
+
+            if complete {
+                self.deliver(sequence);
+            } else if sequence < lost_below {
+                let dropped = self.drain(sequence);
+                self.stats.fragments_lost += dropped.len() as u64 + 1;
+                self.expected = sequence + 1;
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// Removes and returns the fragments from `expected` up to `last`.
+    fn drain(&mut self, last: u64) -> Vec<MultilinkFragment> {
+        let rest = self.fragments.split_off(&(last + 1));
+        let fragments = std::mem::replace(&mut self.fragments, rest);
+
+        fragments.into_values().collect()
+    }
+
+    fn deliver(&mut self, last: u64) {
+        let fragments = self.drain(last);
+        self.expected = last + 1;
+
+        let mut payload = Vec::new();
+        for fragment in &fragments {
+            payload.extend_from_slice(&fragment.data);
+        }
+
+        // The protocol field of the reconstructed packet may be compressed.
+        let framing = Framing {
+            pfc: true,
+            ..Default::default()
+        };
+
+        let mut pkt = PppPkt::default();
+        if payload.len() > usize::from(self.config.mrru) + 2
+            || pkt
+                .deserialize_framed(&mut payload.as_slice(), &framing)
+                .is_err()
+        {
+            self.stats.fragments_lost += fragments.len() as u64;
+            return;
+        }
+
+        self.stats.packets_reassembled += 1;
+        self.packets.push_back(pkt);
+    }
+}
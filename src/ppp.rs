@@ -1,4 +1,7 @@
-use crate::{ChapPkt, Deserialize, Error, IpcpPkt, Ipv6cpPkt, LcpPkt, PapPkt, Result, Serialize};
+use crate::{
+    CcpPkt, ChapPkt, Deserialize, EcpPkt, Error, IpcpPkt, Ipv6cpPkt, LcpPkt, LqrPkt,
+    MultilinkFragment, PapPkt, Result, Serialize,
+};
 
 use std::fmt;
 use std::io::{Read, Write};
@@ -10,12 +13,22 @@ pub const PAP: u16 = 0xc023;
 pub const CHAP: u16 = 0xc223;
 pub const IPCP: u16 = 0x8021;
 pub const IPV6CP: u16 = 0x8057;
+pub const IPV4: u16 = 0x0021;
+pub const IPV6: u16 = 0x0057;
+pub const MULTILINK: u16 = 0x003d;
+pub const CCP: u16 = 0x80fd;
+pub const COMPRESSED_DATAGRAM: u16 = 0x00fd;
+pub const ECP: u16 = 0x8053;
+pub const ENCRYPTED_DATAGRAM: u16 = 0x0053;
 
 pub const LQR: u16 = 0xc025;
 pub const VAN_JACOBSON: u16 = 0x002d;
 
 pub const CHAP_MD5: u8 = 5;
 
+pub const ALL_STATIONS: u8 = 0xff;
+pub const UI: u8 = 0x03;
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ChapAlgorithm {
@@ -357,6 +370,16 @@ pub enum PppData {
     Chap(ChapPkt),
     Ipcp(IpcpPkt),
     Ipv6cp(Ipv6cpPkt),
+    Ipv4(Vec<u8>),
+    Ipv6(Vec<u8>),
+    Multilink(MultilinkFragment),
+    Lqr(LqrPkt),
+    Ccp(CcpPkt),
+    /// Compressed datagram, see [`crate::ccp::compression::Compression`].
+    Compressed(Vec<u8>),
+    Ecp(EcpPkt),
+    /// Datagram encrypted using the algorithm negotiated through ECP.
+    Encrypted(Vec<u8>),
     Unhandled(u16, Vec<u8>),
 }
 
@@ -374,6 +397,14 @@ impl Serialize for PppData {
             Self::Chap(payload) => payload.serialize(w),
             Self::Ipcp(payload) => payload.serialize(w),
             Self::Ipv6cp(payload) => payload.serialize(w),
+            Self::Ipv4(payload) => w.write_all(payload).map_err(Error::from),
+            Self::Ipv6(payload) => w.write_all(payload).map_err(Error::from),
+            Self::Multilink(payload) => payload.serialize(w),
+            Self::Lqr(payload) => payload.serialize(w),
+            Self::Ccp(payload) => payload.serialize(w),
+            Self::Compressed(payload) => w.write_all(payload).map_err(Error::from),
+            Self::Ecp(payload) => payload.serialize(w),
+            Self::Encrypted(payload) => w.write_all(payload).map_err(Error::from),
             Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
         }
     }
@@ -387,6 +418,14 @@ impl PppData {
             Self::Chap(_) => CHAP,
             Self::Ipcp(_) => IPCP,
             Self::Ipv6cp(_) => IPV6CP,
+            Self::Ipv4(_) => IPV4,
+            Self::Ipv6(_) => IPV6,
+            Self::Multilink(_) => MULTILINK,
+            Self::Lqr(_) => LQR,
+            Self::Ccp(_) => CCP,
+            Self::Compressed(_) => COMPRESSED_DATAGRAM,
+            Self::Ecp(_) => ECP,
+            Self::Encrypted(_) => ENCRYPTED_DATAGRAM,
             Self::Unhandled(ty, _) => *ty,
         }
     }
@@ -398,6 +437,24 @@ impl PppData {
             Self::Chap(payload) => payload.len(),
             Self::Ipcp(payload) => payload.len(),
             Self::Ipv6cp(payload) => payload.len(),
+            Self::Ipv4(payload) => payload
+                .len()
+                .try_into()
+                .unwrap_or_else(|_| panic!("ipv4 datagram length {} exceeds 65535", payload.len())),
+            Self::Ipv6(payload) => payload
+                .len()
+                .try_into()
+                .unwrap_or_else(|_| panic!("ipv6 datagram length {} exceeds 65535", payload.len())),
+            Self::Multilink(payload) => payload.len(),
+            Self::Lqr(payload) => payload.len(),
+            Self::Ccp(payload) => payload.len(),
+            Self::Compressed(payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!("compressed datagram length {} exceeds 65535", payload.len())
+            }),
+            Self::Ecp(payload) => payload.len(),
+            Self::Encrypted(payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!("encrypted datagram length {} exceeds 65535", payload.len())
+            }),
             Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
                 panic!(
                     "unhandled ppp protocol {} packet length {} exceeds 65535",
@@ -444,6 +501,54 @@ impl PppData {
                 tmp.deserialize(r)?;
                 *self = Self::Ipv6cp(tmp);
             }
+            IPV4 => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Ipv4(tmp);
+            }
+            IPV6 => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Ipv6(tmp);
+            }
+            MULTILINK => {
+                let mut tmp = MultilinkFragment::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Multilink(tmp);
+            }
+            LQR => {
+                let mut tmp = LqrPkt::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Lqr(tmp);
+            }
+            CCP => {
+                let mut tmp = CcpPkt::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Ccp(tmp);
+            }
+            COMPRESSED_DATAGRAM => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Compressed(tmp);
+            }
+            ECP => {
+                let mut tmp = EcpPkt::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Ecp(tmp);
+            }
+            ENCRYPTED_DATAGRAM => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Encrypted(tmp);
+            }
             _ => {
                 let mut tmp = Vec::new();
 
@@ -456,6 +561,35 @@ impl PppData {
     }
 }
 
+/// Header compression of PPP packets in one direction.
+///
+/// Compression may only be applied to packets sent to a peer that
+/// requested it, so the transmit and receive directions are configured
+/// separately. The default is uncompressed PPPoE framing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Framing {
+    /// Whether packets start with the HDLC Address and Control fields (0xff03).
+    /// PPPoE doesn't use them.
+    pub addr_ctl: bool,
+    /// Protocol-Field-Compression: protocols below 0x100 take a single byte.
+    pub pfc: bool,
+    /// Address-and-Control-Field-Compression: the Address and Control fields
+    /// are omitted from all packets except LCP.
+    pub acfc: bool,
+    /// Multilink fragments use the Short Sequence Number Header Format.
+    pub ssnhf: bool,
+}
+
+impl Framing {
+    /// Returns uncompressed framing for HDLC-like links as described in RFC 1662.
+    pub fn hdlc() -> Self {
+        Self {
+            addr_ctl: true,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PppPkt {
     #[ppproperly(discriminant_for(field = "data", data_type = "u16"))]
@@ -493,6 +627,153 @@ impl PppPkt {
         }
     }
 
+    pub fn new_ipv4(datagram: Vec<u8>) -> Self {
+        Self {
+            data: PppData::Ipv4(datagram),
+        }
+    }
+
+    pub fn new_ipv6(datagram: Vec<u8>) -> Self {
+        Self {
+            data: PppData::Ipv6(datagram),
+        }
+    }
+
+    pub fn new_multilink(fragment: MultilinkFragment) -> Self {
+        Self {
+            data: PppData::Multilink(fragment),
+        }
+    }
+
+    pub fn new_lqr(lqr: LqrPkt) -> Self {
+        Self {
+            data: PppData::Lqr(lqr),
+        }
+    }
+
+    pub fn new_ccp(ccp: CcpPkt) -> Self {
+        Self {
+            data: PppData::Ccp(ccp),
+        }
+    }
+
+    pub fn new_compressed(data: Vec<u8>) -> Self {
+        Self {
+            data: PppData::Compressed(data),
+        }
+    }
+
+    pub fn new_ecp(ecp: EcpPkt) -> Self {
+        Self {
+            data: PppData::Ecp(ecp),
+        }
+    }
+
+    pub fn new_encrypted(data: Vec<u8>) -> Self {
+        Self {
+            data: PppData::Encrypted(data),
+        }
+    }
+
+    /// Returns the protocol number of the packet.
+    pub fn protocol(&self) -> u16 {
+        self.data.discriminant()
+    }
+
+    /// Returns the IPv4 datagram carried by the packet, if any.
+    pub fn ipv4(&self) -> Option<&[u8]> {
+        match &self.data {
+            PppData::Ipv4(datagram) => Some(datagram),
+            _ => None,
+        }
+    }
+
+    /// Returns the IPv6 datagram carried by the packet, if any.
+    pub fn ipv6(&self) -> Option<&[u8]> {
+        match &self.data {
+            PppData::Ipv6(datagram) => Some(datagram),
+            _ => None,
+        }
+    }
+
+    /// Serializes the packet with the header compression of `framing`.
+    pub fn serialize_framed<W: Write>(&self, w: &mut W, framing: &Framing) -> Result<()> {
+        if self.has_addr_ctl(framing) {
+            ALL_STATIONS.serialize(w)?;
+            UI.serialize(w)?;
+        }
+
+        let protocol = self.protocol();
+        if self.has_short_protocol(framing) {
+            (protocol as u8).serialize(w)?;
+        } else {
+            protocol.serialize(w)?;
+        }
+
+        self.data.serialize(w)
+    }
+
+    /// Deserializes a packet framed according to `framing`.
+    /// Uncompressed fields are always accepted.
+    pub fn deserialize_framed<R: Read>(&mut self, r: &mut R, framing: &Framing) -> Result<()> {
+        let mut first = u8::default();
+        first.deserialize(r)?;
+
+        if framing.addr_ctl {
+            if first == ALL_STATIONS {
+                let mut control = u8::default();
+                control.deserialize(r)?;
+
+                if control != UI {
+                    return Err(Error::MissingAddrCtl);
+                }
+
+                first.deserialize(r)?;
+            } else if !framing.acfc {
+                return Err(Error::MissingAddrCtl);
+            }
+        }
+
+        // The least significant bit is only set in the last byte of a protocol.
+        let protocol = if first & 1 == 1 {
+            if !framing.pfc {
+                return Err(Error::UnexpectedPfc(first));
+            }
+
+            first.into()
+        } else {
+            let mut second = u8::default();
+            second.deserialize(r)?;
+
+            u16::from_be_bytes([first, second])
+        };
+
+        if protocol == MULTILINK && framing.ssnhf {
+            let mut fragment = MultilinkFragment::default();
+            fragment.deserialize_short(r)?;
+
+            self.data = PppData::Multilink(fragment);
+            Ok(())
+        } else {
+            self.data.deserialize_with_discriminant(r, &protocol)
+        }
+    }
+
+    /// Returns the serialized length of the packet
+    /// with the header compression of `framing`.
+    pub fn framed_len(&self, framing: &Framing) -> u16 {
+        let mut len = self.data.len() + 1;
+
+        if self.has_addr_ctl(framing) {
+            len += 2;
+        }
+        if !self.has_short_protocol(framing) {
+            len += 1;
+        }
+
+        len
+    }
+
     pub fn len(&self) -> u16 {
         2 + self.data.len()
     }
@@ -500,6 +781,15 @@ impl PppPkt {
     pub fn is_empty(&self) -> bool {
         self.len() == 2
     }
+
+    fn has_addr_ctl(&self, framing: &Framing) -> bool {
+        // RFC 1661 forbids compressing the header of LCP packets.
+        framing.addr_ctl && (!framing.acfc || self.protocol() == LCP)
+    }
+
+    fn has_short_protocol(&self, framing: &Framing) -> bool {
+        framing.pfc && self.protocol() <= 0xff
+    }
 }
 
 impl fmt::Display for PppPkt {
@@ -510,6 +800,21 @@ impl fmt::Display for PppPkt {
             PppData::Chap(chap) => chap.fmt(f),
             PppData::Ipcp(ipcp) => ipcp.fmt(f),
             PppData::Ipv6cp(ipv6cp) => ipv6cp.fmt(f),
+            PppData::Ipv4(datagram) => write!(f, "IPv4 ({} bytes)", datagram.len()),
+            PppData::Ipv6(datagram) => write!(f, "IPv6 ({} bytes)", datagram.len()),
+            PppData::Lqr(lqr) => lqr.fmt(f),
+            PppData::Ccp(ccp) => ccp.fmt(f),
+            PppData::Compressed(data) => write!(f, "Compressed ({} bytes)", data.len()),
+            PppData::Ecp(ecp) => ecp.fmt(f),
+            PppData::Encrypted(data) => write!(f, "Encrypted ({} bytes)", data.len()),
+            PppData::Multilink(fragment) => write!(
+                f,
+                "MP seq={}{}{} ({} bytes)",
+                fragment.sequence,
+                if fragment.begin { " B" } else { "" },
+                if fragment.end { " E" } else { "" },
+                fragment.data.len()
+            ),
             PppData::Unhandled(ty, payload) => writeln!(f, "PPP {}: {:?}", ty, payload),
         }
     }
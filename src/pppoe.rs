@@ -1,5 +1,12 @@
-use crate::{Deserialize, Error, PppPkt, Result, Serialize, VerType};
+pub mod ac;
+pub mod credits;
+pub mod discovery;
+pub mod relay;
+pub mod validate;
 
+use crate::{Deserialize, Error, Framing, Ipv4Addr, PppPkt, Result, Serialize, VerType};
+
+use std::borrow::Cow;
 use std::fmt::{self, Display};
 use std::io::{Read, Write};
 
@@ -7,6 +14,9 @@ use ppproperly_macros::{Deserialize, Serialize};
 
 pub const ETHER_TYPE_PPPOED: u16 = 0x8863;
 pub const ETHER_TYPE_PPPOES: u16 = 0x8864;
+pub const ETHER_TYPE_VLAN: u16 = 0x8100; // IEEE 802.1Q C-Tag
+pub const ETHER_TYPE_QINQ: u16 = 0x88a8; // IEEE 802.1ad S-Tag
+pub const ETHER_TYPE_QINQ_LEGACY: u16 = 0x9100; // Pre-standard S-Tag
 
 pub const PPP: u8 = 0x00; // Pppoe Active Session PPP Packet
 pub const PADI: u8 = 0x09;
@@ -14,6 +24,10 @@ pub const PADO: u8 = 0x07;
 pub const PADR: u8 = 0x19;
 pub const PADS: u8 = 0x65;
 pub const PADT: u8 = 0xa7;
+pub const PADG: u8 = 0x0a;
+pub const PADC: u8 = 0x0b;
+pub const PADM: u8 = 0xd3;
+pub const PADN: u8 = 0xd4;
 
 pub const TAG_AC_COOKIE: u16 = 0x0104;
 pub const TAG_AC_NAME: u16 = 0x0102;
@@ -23,7 +37,10 @@ pub const TAG_CREDIT_SCALE_FACTOR: u16 = 0x0109;
 pub const TAG_END_OF_LIST: u16 = 0x0000;
 pub const TAG_GENERIC_ERROR: u16 = 0x0203;
 pub const TAG_HOST_UNIQ: u16 = 0x0103;
+pub const TAG_HURL: u16 = 0x0111;
+pub const TAG_IP_ROUTE_ADD: u16 = 0x0121;
 pub const TAG_METRICS: u16 = 0x0107;
+pub const TAG_MOTM: u16 = 0x0112;
 pub const TAG_PPP_MAX_PAYLOAD: u16 = 0x0120;
 pub const TAG_RELAY_SESSION_ID: u16 = 0x0110;
 pub const TAG_SEQUENCE_NUMBER: u16 = 0x0108;
@@ -31,7 +48,30 @@ pub const TAG_SERVICE_NAME: u16 = 0x0101;
 pub const TAG_SERVICE_NAME_ERROR: u16 = 0x0201;
 pub const TAG_VENDOR_SPECIFIC: u16 = 0x0105;
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub const BBF_VENDOR_ID: u32 = 3561;
+
+pub const BBF_OPT_AGENT_CIRCUIT_ID: u8 = 0x01;
+pub const BBF_OPT_AGENT_REMOTE_ID: u8 = 0x02;
+pub const BBF_OPT_ACTUAL_DATA_RATE_UPSTREAM: u8 = 0x81;
+pub const BBF_OPT_ACTUAL_DATA_RATE_DOWNSTREAM: u8 = 0x82;
+pub const BBF_OPT_MIN_DATA_RATE_UPSTREAM: u8 = 0x83;
+pub const BBF_OPT_MIN_DATA_RATE_DOWNSTREAM: u8 = 0x84;
+pub const BBF_OPT_ACCESS_LOOP_ENCAPSULATION: u8 = 0x90;
+
+pub const PPPOE_MTU: u16 = 1492; // 1500 byte Ethernet payload minus PPPoE and PPP headers
+pub const DEFAULT_MRU: u16 = 1500;
+
+/// Returns the largest PPP payload that can be sent over a PPPoE session.
+///
+/// Without an RFC 4638 PPP-Max-Payload tag in the PADS the session
+/// is limited to 1492 bytes. `mru` is the MRU the peer has
+/// negotiated using LCP, if any.
+pub fn session_mtu(ppp_max_payload: Option<u16>, mru: Option<u16>) -> u16 {
+    let limit = ppp_max_payload.unwrap_or(PPPOE_MTU).max(PPPOE_MTU);
+    mru.unwrap_or(DEFAULT_MRU).min(limit)
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct MacAddr(pub [u8; 6]);
 
 impl MacAddr {
@@ -121,6 +161,10 @@ pub enum PppoeCode {
     Padr = PADR,
     Pads = PADS,
     Padt = PADT,
+    Padg = PADG,
+    Padc = PADC,
+    Padm = PADM,
+    Padn = PADN,
 }
 
 impl Default for PppoeCode {
@@ -140,6 +184,10 @@ impl TryFrom<u8> for PppoeCode {
             PADR => Ok(Self::Padr),
             PADS => Ok(Self::Pads),
             PADT => Ok(Self::Padt),
+            PADG => Ok(Self::Padg),
+            PADC => Ok(Self::Padc),
+            PADM => Ok(Self::Padm),
+            PADN => Ok(Self::Padn),
             _ => Err(Error::InvalidPppoeCode(code)),
         }
     }
@@ -166,18 +214,22 @@ pub enum PppoeVal {
     AcCookie(Vec<u8>),
     AcName(String),
     AcSystemError(String),
-    Credits,
-    CreditScaleFactor,
+    Credits(Credits),
+    CreditScaleFactor(u16),
     EndOfList,
     GenericError(String),
     HostUniq(Vec<u8>),
-    Metrics,
-    PppMaxPayload,
+    Hurl(String),
+    IpRouteAdd(IpRoute),
+    Metrics(Metrics),
+    Motm(String),
+    PppMaxPayload(u16),
     RelaySessionId(Vec<u8>),
-    SequenceNumber,
+    SequenceNumber(u16),
     ServiceName(String),
     ServiceNameError(String),
-    VendorSpecific(Vec<u8>),
+    VendorSpecific(VendorSpecific),
+    Unhandled(u16, Vec<u8>),
 }
 
 impl Serialize for PppoeVal {
@@ -186,18 +238,22 @@ impl Serialize for PppoeVal {
             Self::AcCookie(payload) => payload.serialize(w),
             Self::AcName(payload) => payload.as_bytes().serialize(w),
             Self::AcSystemError(payload) => payload.as_bytes().serialize(w),
-            Self::Credits => Ok(()),
-            Self::CreditScaleFactor => Ok(()),
+            Self::Credits(payload) => payload.serialize(w),
+            Self::CreditScaleFactor(payload) => payload.serialize(w),
             Self::EndOfList => Ok(()),
             Self::GenericError(payload) => payload.as_bytes().serialize(w),
             Self::HostUniq(payload) => payload.serialize(w),
-            Self::Metrics => Ok(()),
-            Self::PppMaxPayload => Ok(()),
+            Self::Hurl(payload) => payload.as_bytes().serialize(w),
+            Self::IpRouteAdd(payload) => payload.serialize(w),
+            Self::Metrics(payload) => payload.serialize(w),
+            Self::Motm(payload) => payload.as_bytes().serialize(w),
+            Self::PppMaxPayload(payload) => payload.serialize(w),
             Self::RelaySessionId(payload) => payload.serialize(w),
-            Self::SequenceNumber => Ok(()),
+            Self::SequenceNumber(payload) => payload.serialize(w),
             Self::ServiceName(payload) => payload.as_bytes().serialize(w),
             Self::ServiceNameError(payload) => payload.as_bytes().serialize(w),
             Self::VendorSpecific(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
         }
     }
 }
@@ -208,18 +264,22 @@ impl PppoeVal {
             Self::AcCookie(_) => TAG_AC_COOKIE,
             Self::AcName(_) => TAG_AC_NAME,
             Self::AcSystemError(_) => TAG_AC_SYSTEM_ERROR,
-            Self::Credits => TAG_CREDITS,
-            Self::CreditScaleFactor => TAG_CREDIT_SCALE_FACTOR,
+            Self::Credits(_) => TAG_CREDITS,
+            Self::CreditScaleFactor(_) => TAG_CREDIT_SCALE_FACTOR,
             Self::EndOfList => TAG_END_OF_LIST,
             Self::GenericError(_) => TAG_GENERIC_ERROR,
             Self::HostUniq(_) => TAG_HOST_UNIQ,
-            Self::Metrics => TAG_METRICS,
-            Self::PppMaxPayload => TAG_PPP_MAX_PAYLOAD,
+            Self::Hurl(_) => TAG_HURL,
+            Self::IpRouteAdd(_) => TAG_IP_ROUTE_ADD,
+            Self::Metrics(_) => TAG_METRICS,
+            Self::Motm(_) => TAG_MOTM,
+            Self::PppMaxPayload(_) => TAG_PPP_MAX_PAYLOAD,
             Self::RelaySessionId(_) => TAG_RELAY_SESSION_ID,
-            Self::SequenceNumber => TAG_SEQUENCE_NUMBER,
+            Self::SequenceNumber(_) => TAG_SEQUENCE_NUMBER,
             Self::ServiceName(_) => TAG_SERVICE_NAME,
             Self::ServiceNameError(_) => TAG_SERVICE_NAME_ERROR,
             Self::VendorSpecific(_) => TAG_VENDOR_SPECIFIC,
+            Self::Unhandled(ty, _) => *ty,
         }
     }
 
@@ -228,18 +288,28 @@ impl PppoeVal {
             Self::AcCookie(payload) => payload.len().try_into().unwrap(),
             Self::AcName(payload) => payload.len().try_into().unwrap(),
             Self::AcSystemError(payload) => payload.len().try_into().unwrap(),
-            Self::Credits => 0,
-            Self::CreditScaleFactor => 0,
+            Self::Credits(payload) => payload.len(),
+            Self::CreditScaleFactor(_) => 2,
             Self::EndOfList => 0,
             Self::GenericError(payload) => payload.len().try_into().unwrap(),
             Self::HostUniq(payload) => payload.len().try_into().unwrap(),
-            Self::Metrics => 0,
-            Self::PppMaxPayload => 0,
+            Self::Hurl(payload) => payload.len().try_into().unwrap(),
+            Self::IpRouteAdd(payload) => payload.len(),
+            Self::Metrics(payload) => payload.len(),
+            Self::Motm(payload) => payload.len().try_into().unwrap(),
+            Self::PppMaxPayload(_) => 2,
             Self::RelaySessionId(payload) => payload.len().try_into().unwrap(),
-            Self::SequenceNumber => 0,
+            Self::SequenceNumber(_) => 2,
             Self::ServiceName(payload) => payload.len().try_into().unwrap(),
             Self::ServiceNameError(payload) => payload.len().try_into().unwrap(),
-            Self::VendorSpecific(data) => data.len().try_into().unwrap(),
+            Self::VendorSpecific(payload) => payload.len(),
+            Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!(
+                    "unhandled pppoe tag {} length {} exceeds 65535",
+                    *ty,
+                    payload.len()
+                )
+            }),
         }
     }
 
@@ -268,10 +338,22 @@ impl PppoeVal {
                 *self = Self::AcSystemError(String::from_utf8(tmp)?);
             }
             TAG_CREDITS => {
-                *self = Self::Credits;
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = match deserialize_fixed(&tmp, 4)? {
+                    Some(credits) => Self::Credits(credits),
+                    None => Self::Unhandled(*discriminant, tmp),
+                };
             }
             TAG_CREDIT_SCALE_FACTOR => {
-                *self = Self::CreditScaleFactor;
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = match deserialize_fixed(&tmp, 2)? {
+                    Some(credit_scale_factor) => Self::CreditScaleFactor(credit_scale_factor),
+                    None => Self::Unhandled(*discriminant, tmp),
+                };
             }
             TAG_END_OF_LIST => {
                 *self = Self::EndOfList;
@@ -288,11 +370,41 @@ impl PppoeVal {
                 tmp.deserialize(r)?;
                 *self = Self::HostUniq(tmp);
             }
+            TAG_HURL => {
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Hurl(String::from_utf8(tmp)?);
+            }
+            TAG_IP_ROUTE_ADD => {
+                let mut tmp = IpRoute::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::IpRouteAdd(tmp);
+            }
             TAG_METRICS => {
-                *self = Self::Metrics;
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = match deserialize_fixed(&tmp, 10)? {
+                    Some(metrics) => Self::Metrics(metrics),
+                    None => Self::Unhandled(*discriminant, tmp),
+                };
+            }
+            TAG_MOTM => {
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Motm(String::from_utf8(tmp)?);
             }
             TAG_PPP_MAX_PAYLOAD => {
-                *self = Self::PppMaxPayload;
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = match deserialize_fixed(&tmp, 2)? {
+                    Some(ppp_max_payload) => Self::PppMaxPayload(ppp_max_payload),
+                    None => Self::Unhandled(*discriminant, tmp),
+                };
             }
             TAG_RELAY_SESSION_ID => {
                 let mut tmp = Vec::default();
@@ -301,7 +413,13 @@ impl PppoeVal {
                 *self = Self::RelaySessionId(tmp);
             }
             TAG_SEQUENCE_NUMBER => {
-                *self = Self::SequenceNumber;
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = match deserialize_fixed(&tmp, 2)? {
+                    Some(sequence_number) => Self::SequenceNumber(sequence_number),
+                    None => Self::Unhandled(*discriminant, tmp),
+                };
             }
             TAG_SERVICE_NAME => {
                 let mut tmp = Vec::default();
@@ -316,18 +434,431 @@ impl PppoeVal {
                 *self = Self::ServiceNameError(String::from_utf8(tmp)?);
             }
             TAG_VENDOR_SPECIFIC => {
-                let mut tmp = Vec::default();
+                let mut tmp = VendorSpecific::default();
 
                 tmp.deserialize(r)?;
                 *self = Self::VendorSpecific(tmp);
             }
-            _ => return Err(Error::InvalidPppoeTag(*discriminant)),
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(*discriminant, tmp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes the payload of a tag that has a fixed length.
+/// Returns `None` if `buf` is not exactly `len` bytes long
+/// so that the caller can keep the raw tag instead of failing the packet.
+fn deserialize_fixed<T: Default + Deserialize>(buf: &[u8], len: usize) -> Result<Option<T>> {
+    if buf.len() != len {
+        return Ok(None);
+    }
+
+    let mut tmp = T::default();
+    tmp.deserialize(&mut &*buf)?;
+
+    Ok(Some(tmp))
+}
+
+/// RFC 5578 credit grant of a Credits tag.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Credits {
+    /// Forward Credit Notification: credits granted to the receiver.
+    pub fcn: u16,
+    /// Backward Credit Notification: credits the sender has left.
+    pub bcn: u16,
+}
+
+impl Credits {
+    pub fn len(&self) -> u16 {
+        4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// RFC 5578 link metrics of a Metrics tag.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Metrics {
+    /// The link is receive-only.
+    pub receive_only: bool,
+    /// Relative link quality in percent.
+    pub rlq: u8,
+    /// Remaining resources (e.g. battery) in percent.
+    pub resources: u8,
+    /// Latency in milliseconds.
+    pub latency: u16,
+    /// Current data rate in kbps.
+    pub cur_data_rate: u16,
+    /// Maximum data rate in kbps.
+    pub max_data_rate: u16,
+}
+
+impl Metrics {
+    pub fn len(&self) -> u16 {
+        10
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+impl Serialize for Metrics {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        u16::from(self.receive_only).serialize(w)?;
+        self.rlq.serialize(w)?;
+        self.resources.serialize(w)?;
+        self.latency.serialize(w)?;
+        self.cur_data_rate.serialize(w)?;
+        self.max_data_rate.serialize(w)
+    }
+}
+
+impl Deserialize for Metrics {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut flags = u16::default();
+        flags.deserialize(r)?;
+
+        self.receive_only = flags & 0x0001 != 0;
+        self.rlq.deserialize(r)?;
+        self.resources.deserialize(r)?;
+        self.latency.deserialize(r)?;
+        self.cur_data_rate.deserialize(r)?;
+        self.max_data_rate.deserialize(r)
+    }
+}
+
+/// Route pushed by the AC using an IP_Route_Add tag.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IpRoute {
+    pub dst_prefix: Ipv4Addr,
+    pub prefix_len: u32,
+    pub gateway: Ipv4Addr,
+    pub metric: u32,
+}
+
+impl IpRoute {
+    pub fn len(&self) -> u16 {
+        16
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Payload of a Vendor-Specific tag, decoded if the vendor is known.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VendorSpecific {
+    /// Broadband Forum TR-101 access line information.
+    Bbf(Vec<BbfOption>),
+    Unhandled(u32, Vec<u8>),
+}
+
+impl Default for VendorSpecific {
+    fn default() -> Self {
+        Self::Bbf(Vec::new())
+    }
+}
+
+impl Serialize for VendorSpecific {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.vendor_id().serialize(w)?;
+
+        match self {
+            Self::Bbf(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
+        }
+    }
+}
+
+impl Deserialize for VendorSpecific {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut vendor_id = u32::default();
+        vendor_id.deserialize(r)?;
+
+        match vendor_id {
+            BBF_VENDOR_ID => {
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Bbf(tmp);
+            }
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(vendor_id, tmp);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl VendorSpecific {
+    /// Returns the IANA enterprise number identifying the vendor.
+    pub fn vendor_id(&self) -> u32 {
+        match self {
+            Self::Bbf(_) => BBF_VENDOR_ID,
+            Self::Unhandled(vendor_id, _) => *vendor_id,
+        }
+    }
+
+    pub fn len(&self) -> u16 {
+        4 + match self {
+            Self::Bbf(payload) => payload
+                .iter()
+                .map(|option| u16::from(option.len()))
+                .sum::<u16>(),
+            Self::Unhandled(_, payload) => payload.len().try_into().unwrap(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 4
+    }
+
+    /// Returns the decoded TR-101 sub-options, if this is BBF information.
+    pub fn bbf_options(&self) -> &[BbfOption] {
+        match self {
+            Self::Bbf(options) => options,
+            Self::Unhandled(..) => &[],
+        }
+    }
+
+    /// Returns the TR-101 Agent-Circuit-ID identifying the access line.
+    /// Invalid UTF-8 is replaced with U+FFFD.
+    pub fn agent_circuit_id(&self) -> Option<Cow<'_, str>> {
+        self.bbf_options()
+            .iter()
+            .find_map(|option| match &option.value {
+                BbfOpt::AgentCircuitId(agent_circuit_id) => {
+                    Some(String::from_utf8_lossy(agent_circuit_id))
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the TR-101 Agent-Remote-ID identifying the subscriber.
+    /// Invalid UTF-8 is replaced with U+FFFD.
+    pub fn agent_remote_id(&self) -> Option<Cow<'_, str>> {
+        self.bbf_options()
+            .iter()
+            .find_map(|option| match &option.value {
+                BbfOpt::AgentRemoteId(agent_remote_id) => {
+                    Some(String::from_utf8_lossy(agent_remote_id))
+                }
+                _ => None,
+            })
+    }
+}
+
+/// TR-101 sub-option of BBF vendor-specific information.
+/// Data rates are in kbps. The agent IDs are usually, but not necessarily,
+/// printable text and are kept as raw bytes.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BbfOpt {
+    AgentCircuitId(Vec<u8>),
+    AgentRemoteId(Vec<u8>),
+    ActualDataRateUpstream(u32),
+    ActualDataRateDownstream(u32),
+    MinDataRateUpstream(u32),
+    MinDataRateDownstream(u32),
+    AccessLoopEncapsulation(AccessLoopEncapsulation),
+    Unhandled(u8, Vec<u8>),
+}
+
+impl Serialize for BbfOpt {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        match self {
+            Self::AgentCircuitId(payload) => payload.serialize(w),
+            Self::AgentRemoteId(payload) => payload.serialize(w),
+            Self::ActualDataRateUpstream(payload) => payload.serialize(w),
+            Self::ActualDataRateDownstream(payload) => payload.serialize(w),
+            Self::MinDataRateUpstream(payload) => payload.serialize(w),
+            Self::MinDataRateDownstream(payload) => payload.serialize(w),
+            Self::AccessLoopEncapsulation(payload) => payload.serialize(w),
+            Self::Unhandled(_, payload) => w.write_all(payload).map_err(Error::from),
+        }
+    }
+}
+
+impl BbfOpt {
+    fn discriminant(&self) -> u8 {
+        match self {
+            Self::AgentCircuitId(_) => BBF_OPT_AGENT_CIRCUIT_ID,
+            Self::AgentRemoteId(_) => BBF_OPT_AGENT_REMOTE_ID,
+            Self::ActualDataRateUpstream(_) => BBF_OPT_ACTUAL_DATA_RATE_UPSTREAM,
+            Self::ActualDataRateDownstream(_) => BBF_OPT_ACTUAL_DATA_RATE_DOWNSTREAM,
+            Self::MinDataRateUpstream(_) => BBF_OPT_MIN_DATA_RATE_UPSTREAM,
+            Self::MinDataRateDownstream(_) => BBF_OPT_MIN_DATA_RATE_DOWNSTREAM,
+            Self::AccessLoopEncapsulation(_) => BBF_OPT_ACCESS_LOOP_ENCAPSULATION,
+            Self::Unhandled(ty, _) => *ty,
+        }
+    }
+
+    fn len(&self) -> u8 {
+        match self {
+            Self::AgentCircuitId(payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!("agent circuit id length {} exceeds 255", payload.len())
+            }),
+            Self::AgentRemoteId(payload) => payload
+                .len()
+                .try_into()
+                .unwrap_or_else(|_| panic!("agent remote id length {} exceeds 255", payload.len())),
+            Self::ActualDataRateUpstream(_) => 4,
+            Self::ActualDataRateDownstream(_) => 4,
+            Self::MinDataRateUpstream(_) => 4,
+            Self::MinDataRateDownstream(_) => 4,
+            Self::AccessLoopEncapsulation(payload) => payload.len(),
+            Self::Unhandled(ty, payload) => payload.len().try_into().unwrap_or_else(|_| {
+                panic!(
+                    "unhandled bbf option {} length {} exceeds 255",
+                    *ty,
+                    payload.len()
+                )
+            }),
+        }
+    }
+
+    fn deserialize_with_discriminant<R: Read>(
+        &mut self,
+        r: &mut R,
+        discriminant: &u8,
+    ) -> Result<()> {
+        match *discriminant {
+            BBF_OPT_AGENT_CIRCUIT_ID => {
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::AgentCircuitId(tmp);
+            }
+            BBF_OPT_AGENT_REMOTE_ID => {
+                let mut tmp = Vec::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::AgentRemoteId(tmp);
+            }
+            BBF_OPT_ACTUAL_DATA_RATE_UPSTREAM => {
+                let mut tmp = u32::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ActualDataRateUpstream(tmp);
+            }
+            BBF_OPT_ACTUAL_DATA_RATE_DOWNSTREAM => {
+                let mut tmp = u32::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::ActualDataRateDownstream(tmp);
+            }
+            BBF_OPT_MIN_DATA_RATE_UPSTREAM => {
+                let mut tmp = u32::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::MinDataRateUpstream(tmp);
+            }
+            BBF_OPT_MIN_DATA_RATE_DOWNSTREAM => {
+                let mut tmp = u32::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::MinDataRateDownstream(tmp);
+            }
+            BBF_OPT_ACCESS_LOOP_ENCAPSULATION => {
+                let mut tmp = AccessLoopEncapsulation::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::AccessLoopEncapsulation(tmp);
+            }
+            _ => {
+                let mut tmp = Vec::new();
+
+                r.read_to_end(&mut tmp)?;
+                *self = Self::Unhandled(*discriminant, tmp);
+            }
         }
 
         Ok(())
     }
 }
 
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BbfOption {
+    #[ppproperly(discriminant_for(field = "value", data_type = "u8"))]
+    #[ppproperly(len_for(field = "value", offset = 0, data_type = "u8"))]
+    pub value: BbfOpt,
+}
+
+impl BbfOption {
+    pub fn len(&self) -> u8 {
+        2 + self.value.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 2
+    }
+}
+
+impl From<BbfOpt> for BbfOption {
+    fn from(value: BbfOpt) -> Self {
+        Self { value }
+    }
+}
+
+impl Serialize for [BbfOption] {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        for option in self {
+            option.serialize(w)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Deserialize for Vec<BbfOption> {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let mut buf = Vec::new();
+        r.read_to_end(&mut buf)?;
+
+        let mut r = buf.as_slice();
+        while !r.is_empty() {
+            let mut tmp = BbfOption::from(BbfOpt::ActualDataRateUpstream(0));
+
+            tmp.deserialize(&mut r)?;
+            self.push(tmp);
+        }
+
+        Ok(())
+    }
+}
+
+/// TR-101 access loop encapsulation of the subscriber line.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AccessLoopEncapsulation {
+    /// 0x00 for ATM AAL5, 0x01 for Ethernet.
+    pub data_link: u8,
+    pub encaps1: u8,
+    pub encaps2: u8,
+}
+
+impl AccessLoopEncapsulation {
+    pub fn len(&self) -> u8 {
+        3
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct PppoeTag {
     #[ppproperly(discriminant_for(field = "data", data_type = "u16"))]
@@ -383,6 +914,10 @@ pub enum PppoeData {
     Padr(PppoePadr),
     Pads(PppoePads),
     Padt(PppoePadt),
+    Padg(PppoePadg),
+    Padc(PppoePadc),
+    Padm(PppoePadm),
+    Padn(PppoePadn),
 }
 
 impl Default for PppoeData {
@@ -401,11 +936,31 @@ impl Serialize for PppoeData {
             Self::Padr(payload) => payload.serialize(w),
             Self::Pads(payload) => payload.serialize(w),
             Self::Padt(payload) => payload.serialize(w),
+            Self::Padg(payload) => payload.serialize(w),
+            Self::Padc(payload) => payload.serialize(w),
+            Self::Padm(payload) => payload.serialize(w),
+            Self::Padn(payload) => payload.serialize(w),
         }
     }
 }
 
 impl PppoeData {
+    /// Returns the tags of a discovery packet, or nothing for session data.
+    pub fn tags(&self) -> &[PppoeTag] {
+        match self {
+            Self::Ignore | Self::Ppp(_) => &[],
+            Self::Padi(payload) => &payload.tags,
+            Self::Pado(payload) => &payload.tags,
+            Self::Padr(payload) => &payload.tags,
+            Self::Pads(payload) => &payload.tags,
+            Self::Padt(payload) => &payload.tags,
+            Self::Padg(payload) => &payload.tags,
+            Self::Padc(payload) => &payload.tags,
+            Self::Padm(payload) => &payload.tags,
+            Self::Padn(payload) => &payload.tags,
+        }
+    }
+
     fn discriminant(&self) -> u8 {
         match self {
             Self::Ignore => 0xff,
@@ -415,6 +970,10 @@ impl PppoeData {
             Self::Padr(_) => PADR,
             Self::Pads(_) => PADS,
             Self::Padt(_) => PADT,
+            Self::Padg(_) => PADG,
+            Self::Padc(_) => PADC,
+            Self::Padm(_) => PADM,
+            Self::Padn(_) => PADN,
         }
     }
 
@@ -427,6 +986,10 @@ impl PppoeData {
             Self::Padr(payload) => payload.len(),
             Self::Pads(payload) => payload.len(),
             Self::Padt(payload) => payload.len(),
+            Self::Padg(payload) => payload.len(),
+            Self::Padc(payload) => payload.len(),
+            Self::Padm(payload) => payload.len(),
+            Self::Padn(payload) => payload.len(),
         }
     }
 
@@ -472,6 +1035,30 @@ impl PppoeData {
                 tmp.deserialize(r)?;
                 *self = Self::Padt(tmp);
             }
+            PADG => {
+                let mut tmp = PppoePadg::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Padg(tmp);
+            }
+            PADC => {
+                let mut tmp = PppoePadc::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Padc(tmp);
+            }
+            PADM => {
+                let mut tmp = PppoePadm::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Padm(tmp);
+            }
+            PADN => {
+                let mut tmp = PppoePadn::default();
+
+                tmp.deserialize(r)?;
+                *self = Self::Padn(tmp);
+            }
             _ => *self = Self::Ignore,
         }
 
@@ -479,23 +1066,175 @@ impl PppoeData {
     }
 }
 
-#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+/// IEEE 802.1Q VLAN tag.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct VlanTag {
+    /// Tag protocol identifier, usually [`ETHER_TYPE_VLAN`] or [`ETHER_TYPE_QINQ`].
+    pub tpid: u16,
+    /// Priority code point.
+    pub pcp: u8,
+    /// Drop eligible indicator.
+    pub dei: bool,
+    /// VLAN identifier.
+    pub vid: u16,
+}
+
+impl VlanTag {
+    /// Returns an 802.1Q C-Tag with the given VLAN ID and priority 0.
+    pub fn new(vid: u16) -> Self {
+        Self {
+            tpid: ETHER_TYPE_VLAN,
+            pcp: 0,
+            dei: false,
+            vid,
+        }
+    }
+
+    /// Returns an 802.1ad S-Tag with the given VLAN ID and priority 0.
+    pub fn new_outer(vid: u16) -> Self {
+        Self {
+            tpid: ETHER_TYPE_QINQ,
+            ..Self::new(vid)
+        }
+    }
+
+    fn is_tpid(ether_type: u16) -> bool {
+        matches!(
+            ether_type,
+            ETHER_TYPE_VLAN | ETHER_TYPE_QINQ | ETHER_TYPE_QINQ_LEGACY
+        )
+    }
+
+    fn tci(&self) -> u16 {
+        (u16::from(self.pcp & 0x07) << 13) | (u16::from(self.dei) << 12) | (self.vid & 0x0fff)
+    }
+}
+
+impl Serialize for VlanTag {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.tpid.serialize(w)?;
+        self.tci().serialize(w)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct PppoePkt {
     pub dst_mac: MacAddr,
     pub src_mac: MacAddr,
+    /// VLAN tags between the source MAC and the EtherType, outermost first.
+    pub vlans: Vec<VlanTag>,
     pub ether_type: EtherType,
     pub ver_type: VerType,
-    #[ppproperly(discriminant_for(field = "data", data_type = "u8"))]
     pub session_id: u16,
-    #[ppproperly(len_for(field = "data", offset = 0, data_type = "u16"))]
     pub data: PppoeData,
 }
 
+impl Serialize for PppoePkt {
+    fn serialize<W: Write>(&self, w: &mut W) -> Result<()> {
+        self.serialize_header(w, self.data.len())?;
+        self.data.serialize(w)
+    }
+}
+
+impl Deserialize for PppoePkt {
+    fn deserialize<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        let (code, len) = self.deserialize_header(r)?;
+
+        self.data
+            .deserialize_with_discriminant(&mut r.take(len.into()), &code)
+    }
+}
+
+impl PppoePkt {
+    /// Serializes the packet like [`Serialize::serialize`], but applies
+    /// the header compression of `framing` to session data.
+    pub fn serialize_framed<W: Write>(&self, w: &mut W, framing: &Framing) -> Result<()> {
+        match &self.data {
+            PppoeData::Ppp(pkt) => {
+                self.serialize_header(w, pkt.framed_len(framing))?;
+                pkt.serialize_framed(w, framing)
+            }
+            _ => self.serialize(w),
+        }
+    }
+
+    /// Deserializes a packet like [`Deserialize::deserialize`], but expects
+    /// session data to be framed according to `framing`.
+    pub fn deserialize_framed<R: Read>(&mut self, r: &mut R, framing: &Framing) -> Result<()> {
+        let (code, len) = self.deserialize_header(r)?;
+        let mut r = r.take(len.into());
+
+        if code == PPP {
+            let mut pkt = PppPkt::default();
+            pkt.deserialize_framed(&mut r, framing)?;
+
+            self.data = PppoeData::Ppp(pkt);
+            Ok(())
+        } else {
+            self.data.deserialize_with_discriminant(&mut r, &code)
+        }
+    }
+
+    fn serialize_header<W: Write>(&self, w: &mut W, len: u16) -> Result<()> {
+        self.dst_mac.serialize(w)?;
+        self.src_mac.serialize(w)?;
+
+        for vlan in &self.vlans {
+            vlan.serialize(w)?;
+        }
+
+        self.ether_type.serialize(w)?;
+        self.ver_type.serialize(w)?;
+        self.data.discriminant().serialize(w)?;
+        self.session_id.serialize(w)?;
+        len.serialize(w)
+    }
+
+    /// Deserializes everything up to the payload, returning the code and length.
+    fn deserialize_header<R: Read>(&mut self, r: &mut R) -> Result<(u8, u16)> {
+        self.dst_mac.deserialize(r)?;
+        self.src_mac.deserialize(r)?;
+
+        self.vlans.clear();
+
+        let mut ether_type = u16::default();
+        ether_type.deserialize(r)?;
+
+        while VlanTag::is_tpid(ether_type) {
+            let mut tci = u16::default();
+            tci.deserialize(r)?;
+
+            self.vlans.push(VlanTag {
+                tpid: ether_type,
+                pcp: (tci >> 13) as u8,
+                dei: tci & 0x1000 != 0,
+                vid: tci & 0x0fff,
+            });
+
+            ether_type.deserialize(r)?;
+        }
+
+        self.ether_type = ether_type.into();
+        self.ver_type.deserialize(r)?;
+
+        let mut code = u8::default();
+        code.deserialize(r)?;
+
+        self.session_id.deserialize(r)?;
+
+        let mut len = u16::default();
+        len.deserialize(r)?;
+
+        Ok((code, len))
+    }
+}
+
 impl PppoePkt {
     pub fn new_padi(src_mac: MacAddr, tags: Vec<PppoeTag>) -> Self {
         Self {
             dst_mac: MacAddr::BROADCAST,
             src_mac,
+            vlans: Vec::new(),
             ether_type: EtherType::PppoeDiscovery,
             ver_type: VerType::default(),
             session_id: 0,
@@ -507,6 +1246,7 @@ impl PppoePkt {
         Self {
             dst_mac,
             src_mac,
+            vlans: Vec::new(),
             ether_type: EtherType::PppoeDiscovery,
             ver_type: VerType::default(),
             session_id: 0,
@@ -518,6 +1258,7 @@ impl PppoePkt {
         Self {
             dst_mac,
             src_mac,
+            vlans: Vec::new(),
             ether_type: EtherType::PppoeDiscovery,
             ver_type: VerType::default(),
             session_id: 0,
@@ -534,6 +1275,7 @@ impl PppoePkt {
         Self {
             dst_mac,
             src_mac,
+            vlans: Vec::new(),
             ether_type: EtherType::PppoeDiscovery,
             ver_type: VerType::default(),
             session_id,
@@ -550,6 +1292,7 @@ impl PppoePkt {
         Self {
             dst_mac,
             src_mac,
+            vlans: Vec::new(),
             ether_type: EtherType::PppoeDiscovery,
             ver_type: VerType::default(),
             session_id,
@@ -557,16 +1300,155 @@ impl PppoePkt {
         }
     }
 
+    pub fn new_padg(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        tags: Vec<PppoeTag>,
+    ) -> Self {
+        Self::new_session_discovery(
+            dst_mac,
+            src_mac,
+            session_id,
+            PppoeData::Padg(PppoePadg { tags }),
+        )
+    }
+
+    pub fn new_padc(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        tags: Vec<PppoeTag>,
+    ) -> Self {
+        Self::new_session_discovery(
+            dst_mac,
+            src_mac,
+            session_id,
+            PppoeData::Padc(PppoePadc { tags }),
+        )
+    }
+
+    pub fn new_padm(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        tags: Vec<PppoeTag>,
+    ) -> Self {
+        Self::new_session_discovery(
+            dst_mac,
+            src_mac,
+            session_id,
+            PppoeData::Padm(PppoePadm { tags }),
+        )
+    }
+
+    pub fn new_padn(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        tags: Vec<PppoeTag>,
+    ) -> Self {
+        Self::new_session_discovery(
+            dst_mac,
+            src_mac,
+            session_id,
+            PppoeData::Padn(PppoePadn { tags }),
+        )
+    }
+
+    /// Builds one of the RFC 5578 discovery packets that refer to a session.
+    fn new_session_discovery(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        data: PppoeData,
+    ) -> Self {
+        Self {
+            dst_mac,
+            src_mac,
+            vlans: Vec::new(),
+            ether_type: EtherType::PppoeDiscovery,
+            ver_type: VerType::default(),
+            session_id,
+            data,
+        }
+    }
+
     pub fn new_ppp(dst_mac: MacAddr, src_mac: MacAddr, session_id: u16, pkt: PppPkt) -> Self {
         Self {
             dst_mac,
             src_mac,
+            vlans: Vec::new(),
             ether_type: EtherType::PppoeSession,
             ver_type: VerType::default(),
             session_id,
             data: PppoeData::Ppp(pkt),
         }
     }
+
+    /// Wraps an IPv4 datagram in a PPP session frame.
+    pub fn new_ipv4(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        datagram: Vec<u8>,
+    ) -> Self {
+        Self::new_ppp(dst_mac, src_mac, session_id, PppPkt::new_ipv4(datagram))
+    }
+
+    /// Wraps an IPv6 datagram in a PPP session frame.
+    pub fn new_ipv6(
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        datagram: Vec<u8>,
+    ) -> Self {
+        Self::new_ppp(dst_mac, src_mac, session_id, PppPkt::new_ipv6(datagram))
+    }
+
+    /// Returns the IPv4 datagram carried by a PPP session frame, if any.
+    pub fn ipv4(&self) -> Option<&[u8]> {
+        match &self.data {
+            PppoeData::Ppp(pkt) => pkt.ipv4(),
+            _ => None,
+        }
+    }
+
+    /// Returns the IPv6 datagram carried by a PPP session frame, if any.
+    pub fn ipv6(&self) -> Option<&[u8]> {
+        match &self.data {
+            PppoeData::Ppp(pkt) => pkt.ipv6(),
+            _ => None,
+        }
+    }
+
+    /// Returns the packet tagged with the given VLAN stack, outermost first.
+    pub fn with_vlans(mut self, vlans: Vec<VlanTag>) -> Self {
+        self.vlans = vlans;
+        self
+    }
+
+    /// Deserializes a packet like [`Deserialize::deserialize`], but fails with
+    /// [`Error::InvalidPppoeTag`] if it contains a tag this library doesn't know.
+    pub fn deserialize_strict<R: Read>(&mut self, r: &mut R) -> Result<()> {
+        self.deserialize(r)?;
+
+        match self.data.tags().iter().find_map(|tag| match tag.data {
+            PppoeVal::Unhandled(ty, _) => Some(ty),
+            _ => None,
+        }) {
+            Some(ty) => Err(Error::InvalidPppoeTag(ty)),
+            None => Ok(()),
+        }
+    }
+
+    fn fmt_session_discovery(&self, f: &mut fmt::Formatter<'_>, data: &dyn Display) -> fmt::Result {
+        write!(
+            f,
+            "PPPoED dst={} src={} sid={}: {}",
+            self.dst_mac, self.src_mac, self.session_id, data
+        )
+    }
 }
 
 impl Display for PppoePkt {
@@ -607,6 +1489,10 @@ impl Display for PppoePkt {
                 "PPPoED dst={} src={} sid={}: {}",
                 self.dst_mac, self.src_mac, self.session_id, padt
             ),
+            PppoeData::Padg(padg) => self.fmt_session_discovery(f, padg),
+            PppoeData::Padc(padc) => self.fmt_session_discovery(f, padc),
+            PppoeData::Padm(padm) => self.fmt_session_discovery(f, padm),
+            PppoeData::Padn(padn) => self.fmt_session_discovery(f, padn),
         }
     }
 }
@@ -703,6 +1589,13 @@ impl PppoePads {
     pub fn is_empty(&self) -> bool {
         self.tags.is_empty()
     }
+
+    pub fn ppp_max_payload(&self) -> Option<u16> {
+        self.tags.iter().find_map(|tag| match tag.data {
+            PppoeVal::PppMaxPayload(ppp_max_payload) => Some(ppp_max_payload),
+            _ => None,
+        })
+    }
 }
 
 impl Display for PppoePads {
@@ -735,3 +1628,127 @@ impl Display for PppoePadt {
         write!(f, "PADT {:?}", self.tags)
     }
 }
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PppoePadg {
+    pub tags: Vec<PppoeTag>,
+}
+
+impl PppoePadg {
+    pub fn len(&self) -> u16 {
+        self.tags
+            .iter()
+            .map(|tag| tag.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+impl Display for PppoePadg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PADG {:?}", self.tags)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PppoePadc {
+    pub tags: Vec<PppoeTag>,
+}
+
+impl PppoePadc {
+    pub fn len(&self) -> u16 {
+        self.tags
+            .iter()
+            .map(|tag| tag.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+}
+
+impl Display for PppoePadc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PADC {:?}", self.tags)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PppoePadm {
+    pub tags: Vec<PppoeTag>,
+}
+
+impl PppoePadm {
+    pub fn len(&self) -> u16 {
+        self.tags
+            .iter()
+            .map(|tag| tag.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Returns the messages of the minute carried by this PADM.
+    pub fn motms(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().filter_map(|tag| match &tag.data {
+            PppoeVal::Motm(motm) => Some(motm.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the URLs carried by this PADM.
+    pub fn hurls(&self) -> impl Iterator<Item = &str> {
+        self.tags.iter().filter_map(|tag| match &tag.data {
+            PppoeVal::Hurl(hurl) => Some(hurl.as_str()),
+            _ => None,
+        })
+    }
+}
+
+impl Display for PppoePadm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PADM {:?}", self.tags)
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PppoePadn {
+    pub tags: Vec<PppoeTag>,
+}
+
+impl PppoePadn {
+    pub fn len(&self) -> u16 {
+        self.tags
+            .iter()
+            .map(|tag| tag.len())
+            .reduce(|acc, n| acc + n)
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// Returns the routes the AC wants to be added.
+    pub fn routes(&self) -> impl Iterator<Item = &IpRoute> {
+        self.tags.iter().filter_map(|tag| match &tag.data {
+            PppoeVal::IpRouteAdd(route) => Some(route),
+            _ => None,
+        })
+    }
+}
+
+impl Display for PppoePadn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PADN {:?}", self.tags)
+    }
+}
@@ -0,0 +1,380 @@
+use crate::{EtherType, MacAddr, PppoeData, PppoePkt, PppoeTag, PppoeVal, VendorSpecific, VlanTag};
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Changes to the session table of a [`Server`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    SessionStarted {
+        session_id: u16,
+        client_mac: MacAddr,
+        service_name: String,
+        /// TR-101 Agent-Circuit-ID inserted by an intermediate agent.
+        agent_circuit_id: Option<String>,
+        /// TR-101 Agent-Remote-ID inserted by an intermediate agent.
+        agent_remote_id: Option<String>,
+    },
+    SessionTerminated {
+        session_id: u16,
+        client_mac: MacAddr,
+    },
+}
+
+/// Settings of a discovery [`Server`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Local MAC address, used as the source of all frames.
+    pub mac: MacAddr,
+    /// Name sent in the AC-Name tag.
+    pub ac_name: String,
+    /// Services this AC offers. An empty list accepts any service.
+    pub service_names: Vec<String>,
+    /// Key used to generate and validate AC-Cookies.
+    pub cookie_secret: Vec<u8>,
+    /// Largest RFC 4638 PPP-Max-Payload to grant, `None` disables the tag.
+    pub ppp_max_payload: Option<u16>,
+    /// Time after a PADS during which an identical PADR is considered
+    /// a retransmission. Later ones replace the existing session.
+    pub padr_retransmit_window: Duration,
+}
+
+impl Config {
+    pub fn new(mac: MacAddr, ac_name: String, cookie_secret: Vec<u8>) -> Self {
+        Self {
+            mac,
+            ac_name,
+            service_names: Vec::new(),
+            cookie_secret,
+            ppp_max_payload: None,
+            padr_retransmit_window: Duration::from_secs(16),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Session {
+    client_mac: MacAddr,
+    host_uniq: Option<Vec<u8>>,
+    vlans: Vec<VlanTag>,
+    started: Instant,
+}
+
+/// Sans-IO access concentrator side of the PPPoE discovery stage.
+///
+/// Answers PADIs with PADOs and PADRs with PADSs. AC-Cookies are stateless,
+/// so no state is kept for a client before it has sent a valid PADR.
+/// A PADR with the MAC address, VLANs and Host-Uniq of a session started
+/// less than [`Config::padr_retransmit_window`] ago is answered with that
+/// session's PADS. After that window the client is assumed to have restarted
+/// without sending a PADT, so the old session is terminated instead.
+/// Feed received frames into [`Server::handle_packet`] and send
+/// whatever [`Server::poll_transmit`] returns.
+#[derive(Clone, Debug)]
+pub struct Server {
+    config: Config,
+    sessions: BTreeMap<u16, Session>,
+    next_session_id: u16,
+    transmit: VecDeque<PppoePkt>,
+    events: VecDeque<Event>,
+}
+
+impl Server {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            sessions: BTreeMap::new(),
+            next_session_id: 1,
+            transmit: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn handle_packet(&mut self, pkt: &PppoePkt, now: Instant) {
+        if pkt.ether_type != EtherType::PppoeDiscovery {
+            return;
+        }
+
+        let broadcast = pkt.dst_mac == MacAddr::BROADCAST;
+        if !broadcast && pkt.dst_mac != self.config.mac {
+            return;
+        }
+
+        match &pkt.data {
+            PppoeData::Padi(padi) => self.handle_padi(pkt.src_mac, &pkt.vlans, &padi.tags),
+            PppoeData::Padr(padr) if !broadcast => {
+                self.handle_padr(pkt.src_mac, &pkt.vlans, &padr.tags, now)
+            }
+            PppoeData::Padt(_) if !broadcast => self.handle_padt(pkt.src_mac, pkt.session_id),
+            _ => {}
+        }
+    }
+
+    /// Tears down a session from the AC side by sending a PADT to the client.
+    pub fn terminate(&mut self, session_id: u16) {
+        if let Some(session) = self.sessions.remove(&session_id) {
+            self.transmit.push_back(
+                PppoePkt::new_padt(session.client_mac, self.config.mac, session_id, Vec::new())
+                    .with_vlans(session.vlans),
+            );
+            self.events.push_back(Event::SessionTerminated {
+                session_id,
+                client_mac: session.client_mac,
+            });
+        }
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<PppoePkt> {
+        self.transmit.pop_front()
+    }
+
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    /// Returns the client MAC address of an active session.
+    pub fn session(&self, session_id: u16) -> Option<MacAddr> {
+        self.sessions
+            .get(&session_id)
+            .map(|session| session.client_mac)
+    }
+
+    /// Returns all active sessions as (client MAC, session ID) pairs.
+    pub fn sessions(&self) -> impl Iterator<Item = (MacAddr, u16)> + '_ {
+        self.sessions
+            .iter()
+            .map(|(session_id, session)| (session.client_mac, *session_id))
+    }
+
+    /// Returns the AC-Cookie a client with the given MAC address has to echo.
+    pub fn cookie(&self, client_mac: MacAddr) -> Vec<u8> {
+        self.cookie_mac(client_mac).finalize().into_bytes().to_vec()
+    }
+
+    fn handle_padi(&mut self, client_mac: MacAddr, vlans: &[VlanTag], tags: &[PppoeTag]) {
+        let service_name = match service_name(tags) {
+            Some(service_name) => service_name,
+            None => return,
+        };
+
+        if !self.offers(service_name) {
+            return;
+        }
+
+        let mut reply = vec![PppoeVal::AcName(self.config.ac_name.clone()).into()];
+        if service_name.is_empty() && !self.config.service_names.is_empty() {
+            for offered in &self.config.service_names {
+                reply.push(PppoeVal::ServiceName(offered.clone()).into());
+            }
+        } else {
+            reply.push(PppoeVal::ServiceName(service_name.to_owned()).into());
+        }
+
+        reply.push(PppoeVal::AcCookie(self.cookie(client_mac)).into());
+        reply.extend(echoed(tags));
+        reply.extend(self.ppp_max_payload(tags));
+
+        self.transmit.push_back(
+            PppoePkt::new_pado(client_mac, self.config.mac, reply).with_vlans(vlans.to_vec()),
+        );
+    }
+
+    fn handle_padr(
+        &mut self,
+        client_mac: MacAddr,
+        vlans: &[VlanTag],
+        tags: &[PppoeTag],
+        now: Instant,
+    ) {
+        let cookie_valid = tags.iter().any(|tag| match &tag.data {
+            PppoeVal::AcCookie(cookie) => self.cookie_mac(client_mac).verify_slice(cookie).is_ok(),
+            _ => false,
+        });
+
+        if !cookie_valid {
+            return;
+        }
+
+        let service_name = service_name(tags).unwrap_or_default().to_owned();
+
+        let mut reply = vec![PppoeVal::ServiceName(service_name.clone()).into()];
+        reply.extend(echoed(tags));
+        reply.extend(self.ppp_max_payload(tags));
+
+        if !self.offers(&service_name) {
+            reply.push(PppoeVal::ServiceNameError(String::new()).into());
+            self.transmit.push_back(
+                PppoePkt::new_pads(client_mac, self.config.mac, 0, reply)
+                    .with_vlans(vlans.to_vec()),
+            );
+
+            return;
+        }
+
+        let host_uniq = host_uniq(tags);
+        if let Some(session_id) = self.find_session(client_mac, vlans, host_uniq) {
+            if now.duration_since(self.sessions[&session_id].started)
+                <= self.config.padr_retransmit_window
+            {
+                // The PADS got lost, the client is still waiting for it.
+                self.transmit.push_back(
+                    PppoePkt::new_pads(client_mac, self.config.mac, session_id, reply)
+                        .with_vlans(vlans.to_vec()),
+                );
+
+                return;
+            }
+
+            self.terminate(session_id);
+        }
+
+        let session_id = match self.allocate_session_id() {
+            Some(session_id) => session_id,
+            None => {
+                reply.push(PppoeVal::AcSystemError("out of session ids".into()).into());
+                self.transmit.push_back(
+                    PppoePkt::new_pads(client_mac, self.config.mac, 0, reply)
+                        .with_vlans(vlans.to_vec()),
+                );
+
+                return;
+            }
+        };
+
+        self.sessions.insert(
+            session_id,
+            Session {
+                client_mac,
+                host_uniq: host_uniq.map(<[u8]>::to_vec),
+                vlans: vlans.to_vec(),
+                started: now,
+            },
+        );
+
+        self.transmit.push_back(
+            PppoePkt::new_pads(client_mac, self.config.mac, session_id, reply)
+                .with_vlans(vlans.to_vec()),
+        );
+        self.events.push_back(Event::SessionStarted {
+            session_id,
+            client_mac,
+            service_name,
+            agent_circuit_id: vendor_specific(tags)
+                .find_map(VendorSpecific::agent_circuit_id)
+                .map(Cow::into_owned),
+            agent_remote_id: vendor_specific(tags)
+                .find_map(VendorSpecific::agent_remote_id)
+                .map(Cow::into_owned),
+        });
+    }
+
+    fn handle_padt(&mut self, client_mac: MacAddr, session_id: u16) {
+        if self.session(session_id) != Some(client_mac) {
+            return;
+        }
+
+        self.sessions.remove(&session_id);
+        self.events.push_back(Event::SessionTerminated {
+            session_id,
+            client_mac,
+        });
+    }
+
+    fn find_session(
+        &self,
+        client_mac: MacAddr,
+        vlans: &[VlanTag],
+        host_uniq: Option<&[u8]>,
+    ) -> Option<u16> {
+        self.sessions.iter().find_map(|(session_id, session)| {
+            (session.client_mac == client_mac
+                && session.vlans == vlans
+                && session.host_uniq.as_deref() == host_uniq)
+                .then_some(*session_id)
+        })
+    }
+
+    fn allocate_session_id(&mut self) -> Option<u16> {
+        // 0x0000 and 0xffff are reserved by RFC 2516.
+        for _ in 1..0xffff {
+            let session_id = self.next_session_id;
+
+            self.next_session_id = match self.next_session_id {
+                0xfffe => 1,
+                n => n + 1,
+            };
+
+            if !self.sessions.contains_key(&session_id) {
+                return Some(session_id);
+            }
+        }
+
+        None
+    }
+
+    fn ppp_max_payload(&self, tags: &[PppoeTag]) -> Option<PppoeTag> {
+        let limit = self.config.ppp_max_payload?;
+        let requested = tags.iter().find_map(|tag| match tag.data {
+            PppoeVal::PppMaxPayload(ppp_max_payload) => Some(ppp_max_payload),
+            _ => None,
+        })?;
+
+        Some(PppoeVal::PppMaxPayload(requested.min(limit)).into())
+    }
+
+    fn offers(&self, service_name: &str) -> bool {
+        service_name.is_empty()
+            || self.config.service_names.is_empty()
+            || self
+                .config
+                .service_names
+                .iter()
+                .any(|offered| offered == service_name)
+    }
+
+    fn cookie_mac(&self, client_mac: MacAddr) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.config.cookie_secret)
+            .expect("hmac accepts keys of any length");
+        mac.update(&client_mac.0);
+
+        mac
+    }
+}
+
+fn service_name(tags: &[PppoeTag]) -> Option<&str> {
+    tags.iter().find_map(|tag| match &tag.data {
+        PppoeVal::ServiceName(service_name) => Some(service_name.as_str()),
+        _ => None,
+    })
+}
+
+fn host_uniq(tags: &[PppoeTag]) -> Option<&[u8]> {
+    tags.iter().find_map(|tag| match &tag.data {
+        PppoeVal::HostUniq(host_uniq) => Some(host_uniq.as_slice()),
+        _ => None,
+    })
+}
+
+fn vendor_specific(tags: &[PppoeTag]) -> impl Iterator<Item = &VendorSpecific> {
+    tags.iter().filter_map(|tag| match &tag.data {
+        PppoeVal::VendorSpecific(vendor_specific) => Some(vendor_specific),
+        _ => None,
+    })
+}
+
+fn echoed(tags: &[PppoeTag]) -> impl Iterator<Item = PppoeTag> + '_ {
+    tags.iter()
+        .filter(|tag| {
+            matches!(
+                tag.data,
+                PppoeVal::HostUniq(_) | PppoeVal::RelaySessionId(_)
+            )
+        })
+        .cloned()
+}
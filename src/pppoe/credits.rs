@@ -0,0 +1,182 @@
+use crate::{Credits, MacAddr, PppoeData, PppoePkt, PppoeTag, PppoeVal};
+
+pub const DEFAULT_SCALE_FACTOR: u16 = 64;
+
+/// RFC 5578 credit accounting of a single PPPoE session.
+///
+/// Tracks the credits the peer has granted to us (consumed by sending)
+/// and the credits we have granted to the peer (consumed by receiving).
+/// One credit covers `scale_factor` bytes of PPP payload.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Account {
+    scale_factor: u16,
+    tx_credits: u16,
+    rx_credits: u16,
+    sequence_number: u16,
+    pending: Option<u16>,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self::new(DEFAULT_SCALE_FACTOR)
+    }
+}
+
+impl Account {
+    pub fn new(scale_factor: u16) -> Self {
+        Self {
+            scale_factor: scale_factor.max(1),
+            tx_credits: 0,
+            rx_credits: 0,
+            sequence_number: 0,
+            pending: None,
+        }
+    }
+
+    pub fn scale_factor(&self) -> u16 {
+        self.scale_factor
+    }
+
+    /// Returns the number of credits the peer has granted to us.
+    pub fn tx_credits(&self) -> u16 {
+        self.tx_credits
+    }
+
+    /// Returns the number of credits we have granted to the peer.
+    pub fn rx_credits(&self) -> u16 {
+        self.rx_credits
+    }
+
+    /// Returns whether a PADG is waiting for its PADC.
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Returns the number of credits a payload of `len` bytes consumes.
+    pub fn credits_for(&self, len: usize) -> u16 {
+        len.div_ceil(self.scale_factor.into())
+            .try_into()
+            .unwrap_or(u16::MAX)
+    }
+
+    /// Consumes the credits for sending `len` bytes.
+    /// Returns false and consumes nothing if there aren't enough credits.
+    pub fn try_send(&mut self, len: usize) -> bool {
+        match self.tx_credits.checked_sub(self.credits_for(len)) {
+            Some(tx_credits) => {
+                self.tx_credits = tx_credits;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Consumes the peer's credits for `len` received bytes.
+    /// Returns false if the peer has exceeded its credits.
+    pub fn received(&mut self, len: usize) -> bool {
+        match self.rx_credits.checked_sub(self.credits_for(len)) {
+            Some(rx_credits) => {
+                self.rx_credits = rx_credits;
+                true
+            }
+            None => {
+                self.rx_credits = 0;
+                false
+            }
+        }
+    }
+
+    /// Grants `fcn` credits to the peer, returning the PADG to send.
+    pub fn grant(
+        &mut self,
+        dst_mac: MacAddr,
+        src_mac: MacAddr,
+        session_id: u16,
+        fcn: u16,
+    ) -> PppoePkt {
+        self.rx_credits = self.rx_credits.saturating_add(fcn);
+        self.sequence_number = self.sequence_number.wrapping_add(1);
+        self.pending = Some(self.sequence_number);
+
+        PppoePkt::new_padg(
+            dst_mac,
+            src_mac,
+            session_id,
+            vec![
+                PppoeVal::Credits(Credits {
+                    fcn,
+                    bcn: self.tx_credits,
+                })
+                .into(),
+                PppoeVal::SequenceNumber(self.sequence_number).into(),
+            ],
+        )
+    }
+
+    /// Applies a received PADG and returns the PADC to answer it with,
+    /// granting `fcn` credits back to the peer.
+    pub fn handle_padg(&mut self, pkt: &PppoePkt, fcn: u16) -> Option<PppoePkt> {
+        let padg = match &pkt.data {
+            PppoeData::Padg(padg) => padg,
+            _ => return None,
+        };
+
+        let credits = credits(&padg.tags)?;
+        let sequence_number = sequence_number(&padg.tags)?;
+
+        self.tx_credits = self.tx_credits.saturating_add(credits.fcn);
+        self.rx_credits = credits.bcn.saturating_add(fcn);
+
+        Some(PppoePkt::new_padc(
+            pkt.src_mac,
+            pkt.dst_mac,
+            pkt.session_id,
+            vec![
+                PppoeVal::Credits(Credits {
+                    fcn,
+                    bcn: self.tx_credits,
+                })
+                .into(),
+                PppoeVal::SequenceNumber(sequence_number).into(),
+            ],
+        ))
+    }
+
+    /// Applies a received PADC. Returns false if it doesn't answer
+    /// the outstanding PADG.
+    pub fn handle_padc(&mut self, pkt: &PppoePkt) -> bool {
+        let padc = match &pkt.data {
+            PppoeData::Padc(padc) => padc,
+            _ => return false,
+        };
+
+        let (credits, sequence_number) = match (credits(&padc.tags), sequence_number(&padc.tags)) {
+            (Some(credits), Some(sequence_number)) => (credits, sequence_number),
+            _ => return false,
+        };
+
+        if self.pending != Some(sequence_number) {
+            return false;
+        }
+
+        self.pending = None;
+        self.tx_credits = self.tx_credits.saturating_add(credits.fcn);
+        self.rx_credits = credits.bcn;
+
+        true
+    }
+}
+
+fn credits(tags: &[PppoeTag]) -> Option<Credits> {
+    tags.iter().find_map(|tag| match tag.data {
+        PppoeVal::Credits(credits) => Some(credits),
+        _ => None,
+    })
+}
+
+fn sequence_number(tags: &[PppoeTag]) -> Option<u16> {
+    tags.iter().find_map(|tag| match tag.data {
+        PppoeVal::SequenceNumber(sequence_number) => Some(sequence_number),
+        _ => None,
+    })
+}
@@ -0,0 +1,449 @@
+use crate::{EtherType, MacAddr, PppoeData, PppoePkt, PppoeTag, PppoeVal, VlanTag};
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+/// Reasons for the discovery stage to give up.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum Failure {
+    #[error("service name error: {0}")]
+    ServiceNameError(String),
+    #[error("ac system error: {0}")]
+    AcSystemError(String),
+    #[error("generic error: {0}")]
+    GenericError(String),
+    #[error("no acceptable pado received")]
+    NoOffer,
+    #[error("no pads received")]
+    NoSession,
+    #[error("pads carries invalid session id {0}")]
+    InvalidSessionId(u16),
+}
+
+/// Outcome of the discovery stage.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Event {
+    SessionEstablished { session_id: u16, ac_mac: MacAddr },
+    Failed(Failure),
+}
+
+/// An acceptable PADO received during discovery.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Offer {
+    pub ac_mac: MacAddr,
+    pub ac_name: String,
+    pub ac_cookie: Option<Vec<u8>>,
+    pub relay_session_id: Option<Vec<u8>>,
+}
+
+impl Offer {
+    fn from_tags(ac_mac: MacAddr, tags: &[PppoeTag]) -> Self {
+        let mut offer = Self {
+            ac_mac,
+            ac_name: String::new(),
+            ac_cookie: None,
+            relay_session_id: None,
+        };
+
+        for tag in tags {
+            match &tag.data {
+                PppoeVal::AcName(ac_name) => offer.ac_name = ac_name.clone(),
+                PppoeVal::AcCookie(ac_cookie) => offer.ac_cookie = Some(ac_cookie.clone()),
+                PppoeVal::RelaySessionId(relay_session_id) => {
+                    offer.relay_session_id = Some(relay_session_id.clone())
+                }
+                _ => {}
+            }
+        }
+
+        offer
+    }
+}
+
+/// Settings of a discovery [`Client`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Local MAC address, used as the source of all frames.
+    pub mac: MacAddr,
+    /// Service to request. Empty means any service.
+    pub service_name: String,
+    /// Only accept offers from the AC with this name if set.
+    pub ac_name: Option<String>,
+    /// Host-Uniq value used to correlate replies. Empty disables the tag.
+    pub host_uniq: Vec<u8>,
+    /// Wait time before the first retransmission of a PADI or PADR.
+    pub initial_timeout: Duration,
+    /// Upper limit of the exponential backoff.
+    pub max_timeout: Duration,
+    /// Number of PADIs or PADRs to send before giving up.
+    pub max_attempts: u32,
+    /// Time to keep collecting PADOs after the first one has arrived.
+    pub offer_timeout: Duration,
+    /// RFC 4638 PPP-Max-Payload to request for MTUs above 1492.
+    pub ppp_max_payload: Option<u16>,
+    /// VLAN stack to send on, outermost first. Frames received on other VLANs are ignored.
+    pub vlans: Vec<VlanTag>,
+}
+
+impl Config {
+    pub fn new(mac: MacAddr) -> Self {
+        Self {
+            mac,
+            service_name: String::new(),
+            ac_name: None,
+            host_uniq: Vec::new(),
+            initial_timeout: Duration::from_secs(1),
+            max_timeout: Duration::from_secs(16),
+            max_attempts: 5,
+            offer_timeout: Duration::ZERO,
+            ppp_max_payload: None,
+            vlans: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum State {
+    Idle,
+    Discovering {
+        offers: Vec<Offer>,
+        select_at: Option<Instant>,
+    },
+    Requesting {
+        offer: Offer,
+        alternatives: VecDeque<Offer>,
+    },
+    Established,
+    Failed,
+}
+
+/// Sans-IO client side of the PPPoE discovery stage (PADI, PADO, PADR, PADS).
+///
+/// Feed received frames into [`Client::handle_packet`] and call
+/// [`Client::handle_timeout`] once [`Client::poll_timeout`] has passed.
+/// Frames to send are returned by [`Client::poll_transmit`],
+/// the final result by [`Client::poll_event`].
+#[derive(Clone, Debug)]
+pub struct Client {
+    config: Config,
+    state: State,
+    attempt: u32,
+    timeout: Duration,
+    deadline: Option<Instant>,
+    last_error: Option<Failure>,
+    ppp_max_payload: Option<u16>,
+    transmit: VecDeque<PppoePkt>,
+    events: VecDeque<Event>,
+}
+
+impl Client {
+    pub fn new(config: Config) -> Self {
+        Self {
+            timeout: config.initial_timeout,
+            config,
+            state: State::Idle,
+            attempt: 0,
+            deadline: None,
+            last_error: None,
+            ppp_max_payload: None,
+            transmit: VecDeque::new(),
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Starts (or restarts) discovery by broadcasting a PADI.
+    pub fn start(&mut self, now: Instant) {
+        self.state = State::Discovering {
+            offers: Vec::new(),
+            select_at: None,
+        };
+        self.last_error = None;
+
+        self.reset_backoff();
+        self.send_padi(now);
+    }
+
+    pub fn handle_packet(&mut self, pkt: &PppoePkt, now: Instant) {
+        if pkt.ether_type != EtherType::PppoeDiscovery || pkt.dst_mac != self.config.mac {
+            return;
+        }
+
+        let vids = pkt.vlans.iter().map(|vlan| vlan.vid);
+        if !vids.eq(self.config.vlans.iter().map(|vlan| vlan.vid)) {
+            return;
+        }
+
+        match (&self.state, &pkt.data) {
+            (State::Discovering { .. }, PppoeData::Pado(pado)) => {
+                self.handle_pado(pkt.src_mac, &pado.tags, now)
+            }
+            (State::Requesting { .. }, PppoeData::Pads(pads)) => {
+                self.handle_pads(pkt.src_mac, pkt.session_id, &pads.tags)
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_timeout(&mut self, now: Instant) {
+        if let State::Discovering {
+            select_at: Some(select_at),
+            ..
+        } = self.state
+        {
+            if select_at <= now {
+                self.select_offer(now);
+                return;
+            }
+        }
+
+        match self.deadline {
+            Some(deadline) if deadline <= now => {}
+            _ => return,
+        }
+
+        if self.attempt < self.config.max_attempts {
+            self.backoff();
+
+            match &self.state {
+                State::Discovering { .. } => self.send_padi(now),
+                State::Requesting { .. } => self.send_padr(now),
+                _ => {}
+            }
+
+            return;
+        }
+
+        match &mut self.state {
+            State::Discovering { .. } => {
+                let failure = self.last_error.take().unwrap_or(Failure::NoOffer);
+                self.fail(failure);
+            }
+            State::Requesting { alternatives, .. } => match alternatives.pop_front() {
+                Some(offer) => {
+                    let alternatives = std::mem::take(alternatives);
+                    self.request(offer, alternatives, now);
+                }
+                None => self.fail(Failure::NoSession),
+            },
+            _ => {}
+        }
+    }
+
+    /// Returns the point in time at which [`Client::handle_timeout`] needs to be called.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        match self.state {
+            State::Discovering {
+                select_at: Some(select_at),
+                ..
+            } => Some(select_at),
+            State::Discovering { .. } | State::Requesting { .. } => self.deadline,
+            _ => None,
+        }
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<PppoePkt> {
+        self.transmit.pop_front()
+    }
+
+    pub fn poll_event(&mut self) -> Option<Event> {
+        self.events.pop_front()
+    }
+
+    pub fn is_established(&self) -> bool {
+        matches!(self.state, State::Established)
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self.state, State::Failed)
+    }
+
+    /// Returns the PPP-Max-Payload granted by the AC in its PADS.
+    pub fn ppp_max_payload(&self) -> Option<u16> {
+        self.ppp_max_payload
+    }
+
+    fn handle_pado(&mut self, ac_mac: MacAddr, tags: &[PppoeTag], now: Instant) {
+        if !self.host_uniq_matches(tags) {
+            return;
+        }
+
+        if let Some(failure) = error_tag(tags) {
+            self.last_error = Some(failure);
+            return;
+        }
+
+        let offer = Offer::from_tags(ac_mac, tags);
+        if !self.is_acceptable(&offer, tags) {
+            return;
+        }
+
+        if let State::Discovering { offers, select_at } = &mut self.state {
+            if offers.iter().any(|known| known.ac_mac == offer.ac_mac) {
+                return;
+            }
+
+            offers.push(offer);
+
+            if select_at.is_none() {
+                *select_at = Some(now + self.config.offer_timeout);
+                self.deadline = None;
+            }
+        }
+
+        if self.config.offer_timeout.is_zero() {
+            self.select_offer(now);
+        }
+    }
+
+    fn handle_pads(&mut self, ac_mac: MacAddr, session_id: u16, tags: &[PppoeTag]) {
+        match &self.state {
+            State::Requesting { offer, .. } if offer.ac_mac == ac_mac => {}
+            _ => return,
+        }
+
+        if !self.host_uniq_matches(tags) {
+            return;
+        }
+
+        if let Some(failure) = error_tag(tags) {
+            self.fail(failure);
+            return;
+        }
+
+        if session_id == 0 || session_id == 0xffff {
+            self.fail(Failure::InvalidSessionId(session_id));
+            return;
+        }
+
+        self.ppp_max_payload = tags.iter().find_map(|tag| match tag.data {
+            PppoeVal::PppMaxPayload(ppp_max_payload) => Some(ppp_max_payload),
+            _ => None,
+        });
+
+        self.state = State::Established;
+        self.deadline = None;
+        self.events
+            .push_back(Event::SessionEstablished { session_id, ac_mac });
+    }
+
+    fn select_offer(&mut self, now: Instant) {
+        let offers = match &mut self.state {
+            State::Discovering { offers, .. } => std::mem::take(offers),
+            _ => return,
+        };
+
+        let mut offers: VecDeque<Offer> = offers.into();
+        match offers.pop_front() {
+            Some(offer) => self.request(offer, offers, now),
+            None => self.fail(Failure::NoOffer),
+        }
+    }
+
+    fn request(&mut self, offer: Offer, alternatives: VecDeque<Offer>, now: Instant) {
+        self.state = State::Requesting {
+            offer,
+            alternatives,
+        };
+
+        self.reset_backoff();
+        self.send_padr(now);
+    }
+
+    fn send_padi(&mut self, now: Instant) {
+        let mut tags = vec![PppoeVal::ServiceName(self.config.service_name.clone()).into()];
+        if !self.config.host_uniq.is_empty() {
+            tags.push(PppoeVal::HostUniq(self.config.host_uniq.clone()).into());
+        }
+        if let Some(ppp_max_payload) = self.config.ppp_max_payload {
+            tags.push(PppoeVal::PppMaxPayload(ppp_max_payload).into());
+        }
+
+        self.transmit.push_back(
+            PppoePkt::new_padi(self.config.mac, tags).with_vlans(self.config.vlans.clone()),
+        );
+        self.arm(now);
+    }
+
+    fn send_padr(&mut self, now: Instant) {
+        let offer = match &self.state {
+            State::Requesting { offer, .. } => offer,
+            _ => return,
+        };
+
+        let mut tags = vec![PppoeVal::ServiceName(self.config.service_name.clone()).into()];
+        if !self.config.host_uniq.is_empty() {
+            tags.push(PppoeVal::HostUniq(self.config.host_uniq.clone()).into());
+        }
+        if let Some(ppp_max_payload) = self.config.ppp_max_payload {
+            tags.push(PppoeVal::PppMaxPayload(ppp_max_payload).into());
+        }
+        if let Some(ac_cookie) = &offer.ac_cookie {
+            tags.push(PppoeVal::AcCookie(ac_cookie.clone()).into());
+        }
+        if let Some(relay_session_id) = &offer.relay_session_id {
+            tags.push(PppoeVal::RelaySessionId(relay_session_id.clone()).into());
+        }
+
+        self.transmit.push_back(
+            PppoePkt::new_padr(offer.ac_mac, self.config.mac, tags)
+                .with_vlans(self.config.vlans.clone()),
+        );
+        self.arm(now);
+    }
+
+    fn arm(&mut self, now: Instant) {
+        self.attempt += 1;
+        self.deadline = Some(now + self.timeout);
+    }
+
+    fn reset_backoff(&mut self) {
+        self.attempt = 0;
+        self.timeout = self.config.initial_timeout;
+    }
+
+    fn backoff(&mut self) {
+        self.timeout = (self.timeout * 2).min(self.config.max_timeout);
+    }
+
+    fn fail(&mut self, failure: Failure) {
+        self.state = State::Failed;
+        self.deadline = None;
+        self.events.push_back(Event::Failed(failure));
+    }
+
+    fn host_uniq_matches(&self, tags: &[PppoeTag]) -> bool {
+        if self.config.host_uniq.is_empty() {
+            return true;
+        }
+
+        tags.iter().any(|tag| match &tag.data {
+            PppoeVal::HostUniq(host_uniq) => *host_uniq == self.config.host_uniq,
+            _ => false,
+        })
+    }
+
+    fn is_acceptable(&self, offer: &Offer, tags: &[PppoeTag]) -> bool {
+        if let Some(ac_name) = &self.config.ac_name {
+            if offer.ac_name != *ac_name {
+                return false;
+            }
+        }
+
+        self.config.service_name.is_empty()
+            || tags.iter().any(|tag| match &tag.data {
+                PppoeVal::ServiceName(service_name) => *service_name == self.config.service_name,
+                _ => false,
+            })
+    }
+}
+
+fn error_tag(tags: &[PppoeTag]) -> Option<Failure> {
+    tags.iter().find_map(|tag| match &tag.data {
+        PppoeVal::ServiceNameError(msg) => Some(Failure::ServiceNameError(msg.clone())),
+        PppoeVal::AcSystemError(msg) => Some(Failure::AcSystemError(msg.clone())),
+        PppoeVal::GenericError(msg) => Some(Failure::GenericError(msg.clone())),
+        _ => None,
+    })
+}
@@ -0,0 +1,357 @@
+use crate::{
+    BbfOption, EtherType, MacAddr, PppoeData, PppoePkt, PppoeTag, PppoeVal, VendorSpecific, VlanTag,
+};
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// The network a frame is received from or sent to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Side {
+    Subscriber,
+    Ac,
+}
+
+/// Settings of a [`Relay`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    /// Local MAC address, used as the source of all forwarded frames.
+    pub mac: MacAddr,
+    /// Maximum number of discovery exchanges that haven't completed yet.
+    /// The oldest one is forgotten when this is exceeded.
+    pub max_pending: usize,
+    /// VLAN tags of frames sent towards the AC. Subscriber tags are never
+    /// passed on, they are restored on frames sent back to the subscriber.
+    pub ac_vlans: Vec<VlanTag>,
+}
+
+impl Config {
+    pub fn new(mac: MacAddr) -> Self {
+        Self {
+            mac,
+            max_pending: 1024,
+            ac_vlans: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Subscriber {
+    mac: MacAddr,
+    vlans: Vec<VlanTag>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Binding {
+    subscriber: Subscriber,
+    ac_mac: Option<MacAddr>,
+    inserted: bool,
+}
+
+/// Sans-IO PPPoE relay (intermediate agent) as described in RFC 2516.
+///
+/// Frames are forwarded between the subscriber and the AC side with
+/// the MAC addresses rewritten to the relay's own. The VLAN tags a subscriber
+/// is reached through are remembered and replaced by [`Config::ac_vlans`]
+/// towards the AC. Discovery frames are
+/// matched to their subscriber using a Relay-Session-Id tag
+/// the relay inserts into PADIs, and TR-101 line identification
+/// is attached to everything sent towards the AC.
+/// Established sessions are tracked by AC MAC address and session ID.
+///
+/// Feed received frames into [`Relay::handle_subscriber_packet`] or
+/// [`Relay::handle_ac_packet`] and send whatever [`Relay::poll_transmit`]
+/// returns on the indicated side.
+#[derive(Clone, Debug)]
+pub struct Relay {
+    config: Config,
+    pending: BTreeMap<Vec<u8>, Binding>,
+    pending_order: VecDeque<Vec<u8>>,
+    sessions: BTreeMap<(MacAddr, u16), Subscriber>,
+    subscribers: BTreeMap<(MacAddr, u16), MacAddr>,
+    next_relay_session_id: u32,
+    transmit: VecDeque<(Side, PppoePkt)>,
+}
+
+impl Relay {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            pending: BTreeMap::new(),
+            pending_order: VecDeque::new(),
+            sessions: BTreeMap::new(),
+            subscribers: BTreeMap::new(),
+            next_relay_session_id: 1,
+            transmit: VecDeque::new(),
+        }
+    }
+
+    /// Handles a frame received from a subscriber.
+    ///
+    /// `line_id` holds the TR-101 sub-options describing the access line
+    /// the frame was received on. Any BBF Vendor-Specific tags the subscriber
+    /// sent itself are stripped; `line_id` is inserted instead unless empty.
+    pub fn handle_subscriber_packet(&mut self, pkt: &PppoePkt, line_id: &[BbfOption]) {
+        let broadcast = pkt.dst_mac == MacAddr::BROADCAST;
+        if !broadcast && pkt.dst_mac != self.config.mac {
+            return;
+        }
+
+        let key = (pkt.src_mac, pkt.session_id);
+
+        if pkt.ether_type == EtherType::PppoeSession {
+            if let Some(ac_mac) = self.subscribers.get(&key).copied() {
+                self.forward_to_ac(ac_mac, pkt.clone());
+            }
+
+            return;
+        }
+
+        let mut pkt = pkt.clone();
+
+        match &mut pkt.data {
+            PppoeData::Padi(padi) => {
+                let (relay_session_id, inserted) = match relay_session_id(&padi.tags) {
+                    Some(relay_session_id) => (relay_session_id.to_vec(), false),
+                    None => {
+                        let relay_session_id = self.allocate_relay_session_id();
+                        padi.tags
+                            .push(PppoeVal::RelaySessionId(relay_session_id.clone()).into());
+
+                        (relay_session_id, true)
+                    }
+                };
+
+                self.bind(
+                    relay_session_id,
+                    Binding {
+                        subscriber: Subscriber {
+                            mac: pkt.src_mac,
+                            vlans: pkt.vlans.clone(),
+                        },
+                        ac_mac: None,
+                        inserted,
+                    },
+                );
+
+                insert_line_id(&mut padi.tags, line_id);
+                self.forward_to_ac(MacAddr::BROADCAST, pkt);
+            }
+            PppoeData::Padr(padr) if !broadcast => {
+                let ac_mac = match relay_session_id(&padr.tags)
+                    .and_then(|relay_session_id| self.pending.get(relay_session_id))
+                {
+                    Some(Binding {
+                        subscriber,
+                        ac_mac: Some(ac_mac),
+                        ..
+                    }) if subscriber.mac == pkt.src_mac && subscriber.vlans == pkt.vlans => *ac_mac,
+                    _ => return,
+                };
+
+                insert_line_id(&mut padr.tags, line_id);
+                self.forward_to_ac(ac_mac, pkt);
+            }
+            PppoeData::Padt(_) if !broadcast => {
+                if let Some(ac_mac) = self.subscribers.remove(&key) {
+                    self.sessions.remove(&(ac_mac, pkt.session_id));
+                    self.forward_to_ac(ac_mac, pkt);
+                }
+            }
+            PppoeData::Pado(_) | PppoeData::Pads(_) => {}
+            _ if !broadcast => {
+                if let Some(ac_mac) = self.subscribers.get(&key).copied() {
+                    self.forward_to_ac(ac_mac, pkt);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handles a frame received from an access concentrator.
+    ///
+    /// BBF Vendor-Specific tags are stripped before the frame is passed
+    /// on to the subscriber. The Relay-Session-Id inserted by this relay is
+    /// left in PADOs because the subscriber has to echo it in its PADR,
+    /// it is stripped from PADSs.
+    pub fn handle_ac_packet(&mut self, pkt: &PppoePkt) {
+        if pkt.dst_mac != self.config.mac {
+            return;
+        }
+
+        let key = (pkt.src_mac, pkt.session_id);
+
+        if pkt.ether_type == EtherType::PppoeSession {
+            if let Some(subscriber) = self.sessions.get(&key).cloned() {
+                self.forward_to_subscriber(subscriber, pkt.clone());
+            }
+
+            return;
+        }
+
+        let mut pkt = pkt.clone();
+
+        match &mut pkt.data {
+            PppoeData::Pado(pado) => {
+                let relay_session_id = match relay_session_id(&pado.tags) {
+                    Some(relay_session_id) => relay_session_id.to_vec(),
+                    None => return,
+                };
+
+                let binding = match self.pending.get_mut(&relay_session_id) {
+                    Some(binding) => binding,
+                    None => return,
+                };
+
+                let subscriber = binding.subscriber.clone();
+
+                match binding.ac_mac {
+                    None => binding.ac_mac = Some(pkt.src_mac),
+                    Some(ac_mac) if ac_mac == pkt.src_mac => {}
+                    Some(_) if binding.inserted => {
+                        // Another AC answered the same PADI. Give its offer
+                        // a Relay-Session-Id of its own so that the PADR
+                        // selecting it can be told apart.
+                        let binding = Binding {
+                            ac_mac: Some(pkt.src_mac),
+                            ..binding.clone()
+                        };
+
+                        let relay_session_id = self.allocate_relay_session_id();
+                        for tag in &mut pado.tags {
+                            if let PppoeVal::RelaySessionId(payload) = &mut tag.data {
+                                payload.clone_from(&relay_session_id);
+                            }
+                        }
+
+                        self.bind(relay_session_id, binding);
+                    }
+                    Some(_) => binding.ac_mac = Some(pkt.src_mac),
+                }
+
+                strip_line_id(&mut pado.tags);
+                self.forward_to_subscriber(subscriber, pkt);
+            }
+            PppoeData::Pads(pads) => {
+                let relay_session_id = match relay_session_id(&pads.tags) {
+                    Some(relay_session_id) => relay_session_id.to_vec(),
+                    None => return,
+                };
+
+                match self.pending.get(&relay_session_id) {
+                    Some(binding) if binding.ac_mac == Some(pkt.src_mac) => {}
+                    _ => return,
+                }
+
+                let binding = self
+                    .pending
+                    .remove(&relay_session_id)
+                    .expect("binding exists");
+
+                // Session ID 0 indicates an error, no session is created.
+                if pkt.session_id != 0 {
+                    self.sessions.insert(key, binding.subscriber.clone());
+                    self.subscribers
+                        .insert((binding.subscriber.mac, pkt.session_id), pkt.src_mac);
+                }
+
+                strip_line_id(&mut pads.tags);
+                if binding.inserted {
+                    strip_relay_session_id(&mut pads.tags);
+                }
+
+                self.forward_to_subscriber(binding.subscriber, pkt);
+            }
+            PppoeData::Padt(padt) => {
+                if let Some(subscriber) = self.sessions.remove(&key) {
+                    self.subscribers.remove(&(subscriber.mac, pkt.session_id));
+
+                    strip_line_id(&mut padt.tags);
+
+                    self.forward_to_subscriber(subscriber, pkt);
+                }
+            }
+            PppoeData::Padi(_) | PppoeData::Padr(_) => {}
+            _ => {
+                if let Some(subscriber) = self.sessions.get(&key).cloned() {
+                    self.forward_to_subscriber(subscriber, pkt);
+                }
+            }
+        }
+    }
+
+    pub fn poll_transmit(&mut self) -> Option<(Side, PppoePkt)> {
+        self.transmit.pop_front()
+    }
+
+    /// Returns all relayed sessions as (subscriber MAC, AC MAC, session ID) tuples.
+    pub fn sessions(&self) -> impl Iterator<Item = (MacAddr, MacAddr, u16)> + '_ {
+        self.sessions
+            .iter()
+            .map(|((ac_mac, session_id), subscriber)| (subscriber.mac, *ac_mac, *session_id))
+    }
+
+    fn forward_to_ac(&mut self, ac_mac: MacAddr, pkt: PppoePkt) {
+        let vlans = self.config.ac_vlans.clone();
+        self.forward(Side::Ac, ac_mac, pkt.with_vlans(vlans));
+    }
+
+    fn forward_to_subscriber(&mut self, subscriber: Subscriber, pkt: PppoePkt) {
+        self.forward(
+            Side::Subscriber,
+            subscriber.mac,
+            pkt.with_vlans(subscriber.vlans),
+        );
+    }
+
+    fn forward(&mut self, side: Side, dst_mac: MacAddr, mut pkt: PppoePkt) {
+        pkt.dst_mac = dst_mac;
+        pkt.src_mac = self.config.mac;
+
+        self.transmit.push_back((side, pkt));
+    }
+
+    fn bind(&mut self, relay_session_id: Vec<u8>, binding: Binding) {
+        self.pending.insert(relay_session_id.clone(), binding);
+        self.pending_order.push_back(relay_session_id);
+
+        while self.pending_order.len() > self.config.max_pending {
+            if let Some(relay_session_id) = self.pending_order.pop_front() {
+                self.pending.remove(&relay_session_id);
+            }
+        }
+    }
+
+    fn allocate_relay_session_id(&mut self) -> Vec<u8> {
+        loop {
+            let relay_session_id = self.next_relay_session_id.to_be_bytes().to_vec();
+            self.next_relay_session_id = self.next_relay_session_id.wrapping_add(1);
+
+            if !self.pending.contains_key(&relay_session_id) {
+                return relay_session_id;
+            }
+        }
+    }
+}
+
+fn relay_session_id(tags: &[PppoeTag]) -> Option<&[u8]> {
+    tags.iter().find_map(|tag| match &tag.data {
+        PppoeVal::RelaySessionId(relay_session_id) => Some(relay_session_id.as_slice()),
+        _ => None,
+    })
+}
+
+fn insert_line_id(tags: &mut Vec<PppoeTag>, line_id: &[BbfOption]) {
+    strip_line_id(tags);
+
+    if !line_id.is_empty() {
+        tags.push(PppoeVal::VendorSpecific(VendorSpecific::Bbf(line_id.to_vec())).into());
+    }
+}
+
+fn strip_line_id(tags: &mut Vec<PppoeTag>) {
+    tags.retain(|tag| !matches!(tag.data, PppoeVal::VendorSpecific(VendorSpecific::Bbf(_))));
+}
+
+fn strip_relay_session_id(tags: &mut Vec<PppoeTag>) {
+    tags.retain(|tag| !matches!(tag.data, PppoeVal::RelaySessionId(_)));
+}
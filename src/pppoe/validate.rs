@@ -0,0 +1,219 @@
+use crate::{
+    EtherType, MacAddr, PppoeData, PppoePadi, PppoePado, PppoePadr, PppoePads, PppoePadt, PppoePkt,
+    PppoeTag, PppoeVal,
+};
+
+use thiserror::Error;
+
+/// Largest discovery payload, leaving room for a relay to add a tag.
+pub const MAX_DISCOVERY_PAYLOAD: u16 = 1484;
+
+/// RFC 2516 protocol rules a packet can break.
+#[derive(Clone, Debug, Eq, PartialEq, Error)]
+pub enum Violation {
+    #[error("invalid version/type {0:#04x}, expected 0x11")]
+    InvalidVerType(u8),
+    #[error("unknown code")]
+    UnknownCode,
+    #[error("ethertype does not match the code")]
+    EtherTypeMismatch,
+    #[error("destination must be the broadcast address")]
+    NotBroadcast,
+    #[error("destination must be a unicast address")]
+    UnexpectedBroadcast,
+    #[error("invalid session id {0}")]
+    InvalidSessionId(u16),
+    #[error("expected exactly one service name, got {0}")]
+    ServiceNameCount(usize),
+    #[error("missing service name")]
+    MissingServiceName,
+    #[error("missing ac name")]
+    MissingAcName,
+    #[error("discovery payload of {0} bytes exceeds 1484")]
+    PayloadTooLong(u16),
+}
+
+impl PppoePkt {
+    /// Checks the header and payload against the RFC 2516 protocol rules,
+    /// returning the first violation found.
+    pub fn validate(&self) -> Result<(), Violation> {
+        if self.ver_type.ver() != 1 || self.ver_type.ty() != 1 {
+            return Err(Violation::InvalidVerType(self.ver_type.0));
+        }
+
+        // The code isn't kept, unknown ones are decoded as `Ignore`.
+        if self.data == PppoeData::Ignore {
+            return Err(Violation::UnknownCode);
+        }
+
+        let discovery = !matches!(self.data, PppoeData::Ppp(_));
+        let expected = if discovery {
+            EtherType::PppoeDiscovery
+        } else {
+            EtherType::PppoeSession
+        };
+
+        if self.ether_type != expected {
+            return Err(Violation::EtherTypeMismatch);
+        }
+
+        let broadcast = self.dst_mac == MacAddr::BROADCAST;
+
+        match &self.data {
+            PppoeData::Padi(padi) => {
+                if !broadcast {
+                    return Err(Violation::NotBroadcast);
+                }
+                if self.session_id != 0 {
+                    return Err(Violation::InvalidSessionId(self.session_id));
+                }
+
+                padi.validate()
+            }
+            PppoeData::Pado(pado) => {
+                if broadcast {
+                    return Err(Violation::UnexpectedBroadcast);
+                }
+                if self.session_id != 0 {
+                    return Err(Violation::InvalidSessionId(self.session_id));
+                }
+
+                pado.validate()
+            }
+            PppoeData::Padr(padr) => {
+                if broadcast {
+                    return Err(Violation::UnexpectedBroadcast);
+                }
+                if self.session_id != 0 {
+                    return Err(Violation::InvalidSessionId(self.session_id));
+                }
+
+                padr.validate()
+            }
+            PppoeData::Pads(pads) => {
+                if broadcast {
+                    return Err(Violation::UnexpectedBroadcast);
+                }
+
+                // A PADS refusing the request carries an error tag
+                // and a session ID of zero.
+                let valid = if is_error(&pads.tags) {
+                    self.session_id == 0
+                } else {
+                    is_valid_session_id(self.session_id)
+                };
+
+                if !valid {
+                    return Err(Violation::InvalidSessionId(self.session_id));
+                }
+
+                pads.validate()
+            }
+            PppoeData::Padt(padt) => {
+                if broadcast {
+                    return Err(Violation::UnexpectedBroadcast);
+                }
+                if !is_valid_session_id(self.session_id) {
+                    return Err(Violation::InvalidSessionId(self.session_id));
+                }
+
+                padt.validate()
+            }
+            PppoeData::Ppp(_) => Ok(()),
+            _ => validate_payload(self.data.tags()),
+        }
+    }
+}
+
+impl PppoePadi {
+    /// Checks the tags against the RFC 2516 rules for PADIs.
+    pub fn validate(&self) -> Result<(), Violation> {
+        validate_payload(&self.tags)?;
+        validate_service_name(&self.tags)
+    }
+}
+
+impl PppoePado {
+    /// Checks the tags against the RFC 2516 rules for PADOs.
+    pub fn validate(&self) -> Result<(), Violation> {
+        validate_payload(&self.tags)?;
+
+        if !self
+            .tags
+            .iter()
+            .any(|tag| matches!(tag.data, PppoeVal::AcName(_)))
+        {
+            return Err(Violation::MissingAcName);
+        }
+
+        // A PADO may offer more than one service.
+        if !self
+            .tags
+            .iter()
+            .any(|tag| matches!(tag.data, PppoeVal::ServiceName(_)))
+        {
+            return Err(Violation::MissingServiceName);
+        }
+
+        Ok(())
+    }
+}
+
+impl PppoePadr {
+    /// Checks the tags against the RFC 2516 rules for PADRs.
+    pub fn validate(&self) -> Result<(), Violation> {
+        validate_payload(&self.tags)?;
+        validate_service_name(&self.tags)
+    }
+}
+
+impl PppoePads {
+    /// Checks the tags against the RFC 2516 rules for PADSs.
+    pub fn validate(&self) -> Result<(), Violation> {
+        validate_payload(&self.tags)?;
+        validate_service_name(&self.tags)
+    }
+}
+
+impl PppoePadt {
+    /// Checks the tags against the RFC 2516 rules for PADTs.
+    pub fn validate(&self) -> Result<(), Violation> {
+        validate_payload(&self.tags)
+    }
+}
+
+fn validate_payload(tags: &[PppoeTag]) -> Result<(), Violation> {
+    let len = tags.iter().map(|tag| u32::from(tag.len())).sum::<u32>();
+
+    if len > MAX_DISCOVERY_PAYLOAD.into() {
+        return Err(Violation::PayloadTooLong(
+            len.try_into().unwrap_or(u16::MAX),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_service_name(tags: &[PppoeTag]) -> Result<(), Violation> {
+    match tags
+        .iter()
+        .filter(|tag| matches!(tag.data, PppoeVal::ServiceName(_)))
+        .count()
+    {
+        1 => Ok(()),
+        n => Err(Violation::ServiceNameCount(n)),
+    }
+}
+
+fn is_error(tags: &[PppoeTag]) -> bool {
+    tags.iter().any(|tag| {
+        matches!(
+            tag.data,
+            PppoeVal::ServiceNameError(_) | PppoeVal::AcSystemError(_) | PppoeVal::GenericError(_)
+        )
+    })
+}
+
+fn is_valid_session_id(session_id: u16) -> bool {
+    session_id != 0 && session_id != 0xffff
+}